@@ -0,0 +1,269 @@
+// Copyright (c) 2017 cargo-cli developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! `cargo-cli` registry response cache.
+//!
+//! The releases looked up for a crate are kept for a day, one JSON file per crate in a directory
+//! per registry, under `cargo-cli/registry` in the user cache directory, or under
+//! `$CARGO_CLI_HOME/cache` when that variable is set.
+
+use dirs;
+use error::{ErrorKind, Result};
+use registry::Release;
+use serde_json;
+use std::env;
+use std::fs;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use store::CARGO_CLI_HOME;
+use tempfile::NamedTempFile;
+use util::read_to_string;
+
+/// How long a cached lookup is used for.
+pub const CACHE_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// A cached lookup, as stored on disk.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct CacheFile {
+    /// When the releases were fetched, in seconds since the Unix epoch.
+    fetched: u64,
+    /// The releases of the crate.
+    releases: Vec<Release>,
+}
+
+/// A summary of a cached lookup.
+#[derive(Clone, Debug)]
+pub struct Entry {
+    /// The registry the lookup was made on.
+    registry: String,
+    /// The crate name.
+    name: String,
+    /// How long ago the lookup was made.
+    age: Duration,
+    /// The number of releases found.
+    releases: usize,
+}
+
+impl Entry {
+    /// Get the `registry` value.
+    pub fn registry(&self) -> &str {
+        &self.registry
+    }
+
+    /// Get the `name` value.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Get the `age` value.
+    pub fn age(&self) -> Duration {
+        self.age
+    }
+
+    /// Get the `releases` value.
+    pub fn releases(&self) -> usize {
+        self.releases
+    }
+
+    /// Has the lookup outlived the cache TTL?
+    pub fn is_expired(&self) -> bool {
+        self.age > CACHE_TTL
+    }
+}
+
+/// The on-disk cache of registry lookups.
+#[derive(Clone, Debug)]
+pub struct Cache {
+    /// The directory holding the cached lookups.
+    dir: PathBuf,
+}
+
+impl Cache {
+    /// Open the registry lookup cache in the user cache directory.
+    pub fn open() -> Result<Cache> {
+        let dir = if let Some(home) = env::var_os(CARGO_CLI_HOME) {
+            PathBuf::from(home).join("cache")
+        } else if let Some(cache_dir) = dirs::cache_dir() {
+            cache_dir.join("cargo-cli").join("registry")
+        } else {
+            return Err(ErrorKind::NoCacheDir.into());
+        };
+
+        Ok(Cache { dir })
+    }
+
+    /// Get the `dir` value.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Get the cached releases of the given crate on the given registry, if they were fetched
+    /// within the cache TTL.
+    pub fn get(&self, registry: &str, name: &str) -> Option<Vec<Release>> {
+        let cache_file = read_cache_file(&self.path(registry, name)).ok()?;
+
+        if age(cache_file.fetched) > CACHE_TTL {
+            None
+        } else {
+            Some(cache_file.releases)
+        }
+    }
+
+    /// Cache the releases of the given crate on the given registry.
+    pub fn put(&self, registry: &str, name: &str, releases: &[Release]) -> Result<()> {
+        let path = self.path(registry, name);
+        let parent = path.parent().unwrap_or(&self.dir);
+        fs::create_dir_all(parent)?;
+
+        let cache_file = CacheFile {
+            fetched: now(),
+            releases: releases.to_vec(),
+        };
+
+        // Write to a uniquely named temporary file first, so concurrent runs and lookup threads
+        // never read a partial file or write to the same one.
+        let mut staging = NamedTempFile::new_in(parent)?;
+        {
+            let mut file_writer = BufWriter::new(staging.as_file_mut());
+            file_writer.write_all(serde_json::to_string(&cache_file)?.as_bytes())?;
+        }
+        staging.persist(&path).map_err(|e| e.error)?;
+
+        Ok(())
+    }
+
+    /// List the cached lookups, sorted by registry and crate name.
+    pub fn list(&self) -> Result<Vec<Entry>> {
+        let mut entries = Vec::new();
+
+        for (registry, name, path) in self.files()? {
+            if let Ok(cache_file) = read_cache_file(&path) {
+                entries.push(Entry {
+                    registry,
+                    name,
+                    age: age(cache_file.fetched),
+                    releases: cache_file.releases.len(),
+                });
+            }
+        }
+
+        entries.sort_by(|a, b| (&a.registry, &a.name).cmp(&(&b.registry, &b.name)));
+        Ok(entries)
+    }
+
+    /// Remove the cached lookups, or only the expired ones.  Returns the number removed.
+    pub fn clear(&self, expired_only: bool) -> Result<usize> {
+        let mut removed = 0;
+
+        for (_, _, path) in self.files()? {
+            let expired = read_cache_file(&path)
+                .map(|cache_file| age(cache_file.fetched) > CACHE_TTL)
+                .unwrap_or(true);
+
+            if expired || !expired_only {
+                fs::remove_file(&path)?;
+                removed += 1;
+            }
+        }
+
+        Ok(removed)
+    }
+
+    /// Get the path of the cache file for the given crate on the given registry.
+    fn path(&self, registry: &str, name: &str) -> PathBuf {
+        self.dir
+            .join(registry)
+            .join(format!("{}.json", name.to_lowercase()))
+    }
+
+    /// Get the cache files, as (registry, crate name, path).
+    fn files(&self) -> Result<Vec<(String, String, PathBuf)>> {
+        let mut files = Vec::new();
+
+        if !self.dir.is_dir() {
+            return Ok(files);
+        }
+
+        for registry_entry in fs::read_dir(&self.dir)? {
+            let registry_entry = registry_entry?;
+            if !registry_entry.file_type()?.is_dir() {
+                continue;
+            }
+            let registry = registry_entry.file_name().to_string_lossy().into_owned();
+
+            for entry in fs::read_dir(registry_entry.path())? {
+                let path = entry?.path();
+                if path.extension().is_some_and(|ext| ext == "json") {
+                    if let Some(name) = path.file_stem() {
+                        let name = name.to_string_lossy().into_owned();
+                        files.push((registry.clone(), name, path.clone()));
+                    }
+                }
+            }
+        }
+
+        Ok(files)
+    }
+}
+
+/// Read a cache file.
+fn read_cache_file(path: &Path) -> Result<CacheFile> {
+    Ok(serde_json::from_str(&read_to_string(path)?)?)
+}
+
+/// Get the current time, in seconds since the Unix epoch.
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|since_epoch| since_epoch.as_secs())
+        .unwrap_or(0)
+}
+
+/// Get the time passed since the given time, in seconds since the Unix epoch.
+fn age(fetched: u64) -> Duration {
+    Duration::from_secs(now().saturating_sub(fetched))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Cache;
+    use registry::Release;
+    use serde_json;
+    use std::thread;
+    use tempfile::TempDir;
+
+    #[test]
+    fn concurrent_puts_of_a_crate_all_succeed() {
+        let dir = TempDir::new().expect("Unable to create the directory!");
+        let cache = Cache {
+            dir: dir.path().to_path_buf(),
+        };
+        let releases: Vec<Release> =
+            serde_json::from_str(r#"[{"vers":"1.0.0"},{"vers":"1.1.0","yanked":true}]"#)
+                .expect("Unable to parse the releases!");
+
+        thread::scope(|scope| {
+            let handles: Vec<_> = (0..8)
+                .map(|_| scope.spawn(|| cache.put("crates.io", "demo", &releases)))
+                .collect();
+            for handle in handles {
+                handle
+                    .join()
+                    .expect("Cache thread panicked!")
+                    .expect("Unable to cache the releases!");
+            }
+        });
+
+        let cached = cache.get("crates.io", "demo").expect("Nothing was cached!");
+        assert_eq!(cached.len(), 2);
+        let entries = cache.list().expect("Unable to list the cache!");
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].name(), "demo");
+    }
+}
@@ -9,40 +9,210 @@
 //! `cargo-cli` errors
 error_chain! {
     foreign_links {
-        Curl(::curl::Error);
-        FromUtf8(::std::string::FromUtf8Error);
-        Io(::std::io::Error);
-        Json(::serde_json::Error);
-        Mustache(::mustache::Error);
-        Term(::term::Error);
-        TomlDe(::toml::de::Error);
-        TomlSe(::toml::ser::Error);
+        Curl(::curl::Error) #[doc = "An HTTP request error."];
+        FromUtf8(::std::string::FromUtf8Error) #[doc = "A UTF-8 conversion error."];
+        Io(::std::io::Error) #[doc = "An I/O error."];
+        Json(::serde_json::Error) #[doc = "A JSON deserialization error."];
+        Mustache(::mustache::Error) #[doc = "A template rendering error."];
+        Term(::term::Error) #[doc = "A terminal error."];
+        TomlDe(::toml::de::Error) #[doc = "A TOML deserialization error."];
+        TomlSe(::toml::ser::Error) #[doc = "A TOML serialization error."];
+        TomlEdit(::toml_edit::TomlError) #[doc = "A TOML parse error."];
     }
 
     errors {
+        /// The cargo command failed.
+        CargoCommand(command: String, status: String, output: String) {
+            description("The cargo command failed!")
+            display("'{}' failed ({}):\n{}", command, status, output)
+        }
+        /// The crate was not found in the registry.
+        CrateNotFound(name: String) {
+            description("The crate was not found in the registry!")
+            display("The crate '{}' was not found in the registry!", name)
+        }
+        /// Unable to clone the template pack repository.
+        GitClone(path: String, output: String) {
+            description("Unable to clone the template pack repository!")
+            display("Unable to clone the template pack repository '{}':\n{}", path, output)
+        }
+        /// The edition is not supported by the Rust version.
+        IncompatibleEdition(edition: String, rust_version: String) {
+            description("The edition is not supported by the Rust version!")
+            display("The {} edition is not supported by Rust {}!", edition, rust_version)
+        }
+        /// An invalid argument parser was specified.
         InvalidArgParser {
             description("An invalid argument parser was specified!")
             display("An invalid argument parser was specified!")
         }
-        InvalidExitCode {
-            description("An invalid exit code was received from 'cargo new'!")
-            display("An invalid exit code was received from 'cargo new'!")
+        /// An invalid template condition was specified.
+        InvalidCondition(condition: String) {
+            description("An invalid template condition was specified!")
+            display("An invalid template condition was specified: '{}'", condition)
+        }
+        /// An invalid edition was specified.
+        InvalidEdition(edition: String) {
+            description("An invalid edition was specified!")
+            display("An invalid edition was specified: '{}'", edition)
+        }
+        /// An invalid error handling crate was specified.
+        InvalidErrorCrate {
+            description("An invalid error handling crate was specified!")
+            display("An invalid error handling crate was specified!")
+        }
+        /// An invalid action for existing files was specified.
+        InvalidExisting(action: String) {
+            description("An invalid action for existing files was specified!")
+            display("An invalid action for existing files was specified: '{}'", action)
+        }
+        /// An invalid project layout was specified.
+        InvalidLayout(layout: String) {
+            description("An invalid project layout was specified!")
+            display("An invalid project layout was specified: '{}'", layout)
         }
-        InvalidLicense {
-            description("An invalid license type was specified!")
-            display("An invalid license type was specified!")
+        /// An invalid license was specified.
+        InvalidLicense(license: String) {
+            description("An invalid license was specified!")
+            display(
+                "An invalid license was specified: '{}', expected both, none or SPDX identifiers \
+                 joined by OR!",
+                license
+            )
         }
+        /// A table in Cargo.toml has an unexpected type.
+        InvalidManifestTable(table: String) {
+            description("A table in Cargo.toml has an unexpected type!")
+            display("The '{}' table in Cargo.toml has an unexpected type!", table)
+        }
+        /// An invalid package name was specified.
+        InvalidPackageName(name: String, reason: String) {
+            description("An invalid package name was specified!")
+            display("The package name '{}' is invalid, {}!", name, reason)
+        }
+        /// An invalid path was specified.
         InvalidPath {
             description("An invalid path was specified!")
             display("An invalid path was specified!")
         }
+        /// An invalid Rust version was specified.
+        InvalidRustVersion(rust_version: String) {
+            description("An invalid Rust version was specified!")
+            display("An invalid Rust version was specified (expected X.Y): '{}'", rust_version)
+        }
+        /// An invalid subcommand was specified.
         InvalidSubCommand {
             description("An invalid subcommand was specified!")
             display("An invalid subcommand was specified!")
         }
+        /// An invalid template directory was specified.
+        InvalidTemplateDir(path: String) {
+            description("An invalid template directory was specified!")
+            display("An invalid template directory was specified: '{}'", path)
+        }
+        /// An invalid template pack name was specified.
+        InvalidTemplateName(name: String) {
+            description("An invalid template pack name was specified!")
+            display("An invalid template pack name was specified: '{}'", name)
+        }
+        /// The directory does not hold a template pack.
+        InvalidTemplatePack(path: String) {
+            description("The directory does not hold a template pack!")
+            display("No template.toml was found in the template pack: '{}'", path)
+        }
+        /// A template pack path leaves the project or pack directory.
+        InvalidTemplatePath(path: String) {
+            description("A template pack path leaves the project or pack directory!")
+            display("The template pack path '{}' leaves the project or pack directory!", path)
+        }
+        /// An invalid template variable was specified.
+        InvalidTemplateVar(var: String) {
+            description("An invalid template variable was specified!")
+            display("An invalid template variable was specified (expected KEY=VALUE): '{}'", var)
+        }
+        /// An invalid version control system was specified.
+        InvalidVcs(vcs: String) {
+            description("An invalid version control system was specified!")
+            display("An invalid version control system was specified: '{}'", vcs)
+        }
+        /// A manifest can't be merged with conflict markers.
+        ManifestMerge(path: String) {
+            description("A manifest can't be merged with conflict markers!")
+            display(
+                "'{}' can't be merged, as conflict markers would leave it invalid, skip or \
+                 overwrite it instead!",
+                path
+            )
+        }
+        /// A template referenced by the template pack is missing.
+        MissingTemplate(path: String) {
+            description("A template referenced by the template pack is missing!")
+            display("A template referenced by the template pack is missing: '{}'", path)
+        }
+        /// A template variable required by the template pack is missing.
+        MissingTemplateVar(var: String, path: String) {
+            description("A template variable required by the template pack is missing!")
+            display("The template variable '{}' required by '{}' is missing!", var, path)
+        }
+        /// Unable to determine the cargo home directory.
+        NoCargoHome {
+            description("Unable to determine the cargo home directory!")
+            display("Unable to determine the cargo home directory, set CARGO_HOME instead!")
+        }
+        /// Unable to determine the user cache directory.
+        NoCacheDir {
+            description("Unable to determine the user cache directory!")
+            display("Unable to determine the user cache directory, set CARGO_CLI_HOME instead!")
+        }
+        /// Unable to determine the user data directory.
+        NoDataDir {
+            description("Unable to determine the user data directory!")
+            display("Unable to determine the user data directory, set CARGO_CLI_HOME instead!")
+        }
+        /// The package already exists.
+        PackageExists(path: String) {
+            description("The package already exists!")
+            display("A package already exists at '{}'!", path)
+        }
+        /// The registry request failed.
+        RegistryRequest(url: String, code: u32) {
+            description("The registry request failed!")
+            display("The registry request for '{}' failed with HTTP status {}!", url, code)
+        }
+        /// Template packs can't be used with the split layout.
+        SplitTemplatePack {
+            description("Template packs can't be used with the split layout!")
+            display("Template packs can't be used with the split layout!")
+        }
+        /// The split layout can't be created inside a workspace.
+        SplitWorkspace(path: String) {
+            description("The split layout can't be created inside a workspace!")
+            display("The split layout creates a workspace, which can't be nested in '{}'!", path)
+        }
+        /// A template pack with the given name is already installed.
+        TemplateExists(name: String) {
+            description("A template pack with the given name is already installed!")
+            display("A template pack named '{}' is already installed, use --force!", name)
+        }
+        /// Issue with term command.
         TermCommand {
             description("Issue with term command!")
             display("Issue with term command!")
         }
+        /// An unknown registry was specified.
+        UnknownRegistry(name: String) {
+            description("An unknown registry was specified!")
+            display("The registry '{}' is not configured in .cargo/config.toml!", name)
+        }
+        /// An unknown template pack was specified.
+        UnknownTemplate(name: String) {
+            description("An unknown template pack was specified!")
+            display("An unknown template pack was specified: '{}'", name)
+        }
+        /// The version control command failed.
+        VcsCommand(command: String, output: String) {
+            description("The version control command failed!")
+            display("The version control command '{}' failed: {}", command, output)
+        }
     }
 }
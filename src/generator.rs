@@ -0,0 +1,1031 @@
+// Copyright (c) 2017 cargo-cli developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! `cargo-cli` project generation.
+//!
+//! A [`ProjectSpec`] describes the command line application to create.  The [`Generator`] built
+//! from it creates the package with `cargo new` (or `cargo init`, or natively), and then renders
+//! everything generated on top of it into a [`Stage`], which holds the generated files and
+//! manifest edits until they're written.
+
+use error::{ErrorKind, Result};
+use license::{parse_licenses, License};
+use manifest::{DepTable, Dependency, Manifest, ManifestPatch};
+use output::{self, Destination, FileGenerator};
+use package::{init_vcs, Package, Vcs};
+use registry::{parse_rust_version, Choice, Registry};
+use stage::{existing_files, Rollback, Stage, CARGO_FILES};
+use std::collections::{BTreeMap, BTreeSet};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use tempfile::TempDir;
+use tmpl::{ArgParser, Edition, ErrorCrate, Layout, Templates};
+use workspace::{Workspace, INHERITABLE_KEYS};
+
+/// A description of the command line application project to create.
+#[derive(Clone, Debug)]
+pub struct ProjectSpec {
+    /// The project directory.
+    path: PathBuf,
+    /// The package name, if it isn't named after the project directory.
+    name: Option<String>,
+    /// The argument parser used by the generated output.
+    arg_parser: ArgParser,
+    /// The error handling crate used by the generated output.
+    errors: ErrorCrate,
+    /// The licenses, of which the user may choose.
+    licenses: Vec<License>,
+    /// Should a README.md be generated?
+    readme: bool,
+    /// The Rust edition, if it isn't the newest one the Rust version supports.
+    edition: Option<Edition>,
+    /// The minimum supported Rust version.
+    rust_version: Option<String>,
+    /// The layout of the generated project.
+    layout: Layout,
+    /// The template directory (or template pack) to load.
+    template_dir: Option<PathBuf>,
+    /// Additional template variables.
+    vars: BTreeMap<String, String>,
+    /// Should we query for the latest version of the dependencies?
+    query: bool,
+    /// Should the latest versions come from the local registry cache only?
+    offline: bool,
+    /// May pre-release versions be chosen?
+    allow_prerelease: bool,
+    /// The alternate registry the dependencies come from.
+    registry: Option<String>,
+    /// The version control system to initialize.
+    vcs: Vcs,
+    /// Should the package be created without running `cargo`?
+    native: bool,
+    /// Should the project be created in an existing directory, like `cargo init`?
+    init: bool,
+    /// Should the project be created in a temporary directory, for a dry run?
+    dry_run: bool,
+    /// Additional arguments passed through to `cargo`.
+    cargo_args: Vec<String>,
+}
+
+impl ProjectSpec {
+    /// Create a new description of a git versioned project at the given path, using clap,
+    /// error-chain, the MIT OR Apache-2.0 licenses and a README.md.
+    pub fn new(path: &Path) -> ProjectSpec {
+        ProjectSpec {
+            path: path.to_path_buf(),
+            name: None,
+            arg_parser: ArgParser::Clap,
+            errors: ErrorCrate::ErrorChain,
+            licenses: vec![License::Mit, License::Apache],
+            readme: true,
+            edition: None,
+            rust_version: None,
+            layout: Layout::Default,
+            template_dir: None,
+            vars: BTreeMap::new(),
+            query: true,
+            offline: false,
+            allow_prerelease: false,
+            registry: None,
+            vcs: Vcs::Git,
+            native: false,
+            init: false,
+            dry_run: false,
+            cargo_args: Vec::new(),
+        }
+    }
+
+    /// Set the package name, which defaults to the name of the project directory.
+    pub fn name(mut self, name: &str) -> ProjectSpec {
+        self.name = Some(name.to_string());
+        self
+    }
+
+    /// Set the argument parser used by the generated output.
+    pub fn arg_parser(mut self, arg_parser: ArgParser) -> ProjectSpec {
+        self.arg_parser = arg_parser;
+        self
+    }
+
+    /// Set the error handling crate used by the generated output.
+    pub fn errors(mut self, errors: ErrorCrate) -> ProjectSpec {
+        self.errors = errors;
+        self
+    }
+
+    /// Set the licenses to include, of which the user may choose, or none at all.
+    pub fn licenses(mut self, licenses: &[License]) -> ProjectSpec {
+        self.licenses = licenses.to_vec();
+        self
+    }
+
+    /// Turn README.md generation on or off.
+    pub fn readme(mut self, readme: bool) -> ProjectSpec {
+        self.readme = readme;
+        self
+    }
+
+    /// Set the Rust edition of the generated output.
+    pub fn edition(mut self, edition: Edition) -> ProjectSpec {
+        self.edition = Some(edition);
+        self
+    }
+
+    /// Set the minimum supported Rust version of the generated output, as `X.Y` or `X.Y.Z`.
+    pub fn rust_version(mut self, rust_version: &str) -> ProjectSpec {
+        self.rust_version = Some(rust_version.to_string());
+        self
+    }
+
+    /// Set the layout of the generated project.
+    pub fn layout(mut self, layout: Layout) -> ProjectSpec {
+        self.layout = layout;
+        self
+    }
+
+    /// Load templates from the given directory, or the template pack it holds.
+    pub fn template_dir(mut self, template_dir: &Path) -> ProjectSpec {
+        self.template_dir = Some(template_dir.to_path_buf());
+        self
+    }
+
+    /// Set a template variable.
+    pub fn var(mut self, key: &str, value: &str) -> ProjectSpec {
+        self.vars.insert(key.to_string(), value.to_string());
+        self
+    }
+
+    /// Turn the query for the latest dependency versions on or off.
+    pub fn query(mut self, query: bool) -> ProjectSpec {
+        self.query = query;
+        self
+    }
+
+    /// Run without accessing the network, resolving the latest versions from the local registry
+    /// cache.
+    pub fn offline(mut self, offline: bool) -> ProjectSpec {
+        self.offline = offline;
+        self
+    }
+
+    /// Allow pre-release dependency versions to be chosen.
+    pub fn allow_prerelease(mut self, allow_prerelease: bool) -> ProjectSpec {
+        self.allow_prerelease = allow_prerelease;
+        self
+    }
+
+    /// Take the dependencies from the given alternate registry.
+    pub fn registry(mut self, registry: &str) -> ProjectSpec {
+        self.registry = Some(registry.to_string());
+        self
+    }
+
+    /// Set the version control system to initialize.
+    pub fn vcs(mut self, vcs: Vcs) -> ProjectSpec {
+        self.vcs = vcs;
+        self
+    }
+
+    /// Create the package without running `cargo`.
+    pub fn native(mut self, native: bool) -> ProjectSpec {
+        self.native = native;
+        self
+    }
+
+    /// Create the project in an existing directory, like `cargo init`.
+    pub fn init(mut self, init: bool) -> ProjectSpec {
+        self.init = init;
+        self
+    }
+
+    /// Create the project in a temporary directory, so the generated stage can be reviewed
+    /// without writing anything where the project goes.
+    pub fn dry_run(mut self, dry_run: bool) -> ProjectSpec {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Pass an additional argument through to `cargo`, i.e. `--locked` or `-v`.
+    pub fn cargo_arg(mut self, arg: &str) -> ProjectSpec {
+        self.cargo_args.push(arg.to_string());
+        self
+    }
+}
+
+/// Creates the project described by a [`ProjectSpec`] and generates the command line application
+/// on top of it.
+pub struct Generator {
+    /// The project description.
+    spec: ProjectSpec,
+    /// The package name.
+    name: String,
+    /// The directory the project is created in, which is a temporary one for a dry run.
+    project_path: PathBuf,
+    /// The temporary directory of a dry run, which is removed when dropped.
+    _temp_dir: Option<TempDir>,
+    /// The Rust edition of the generated output, if not the `cargo` default.
+    edition: Option<Edition>,
+    /// The templates the files are rendered from.
+    templates: Templates,
+    /// The generators registered for additional files.
+    file_generators: Vec<Box<dyn FileGenerator>>,
+    /// The workspace enclosing the project.
+    workspace: Option<Workspace>,
+    /// Was the project added to the `members` of the enclosing workspace?
+    member_added: bool,
+    /// The files `cargo` may create or change, relative to the project directory.
+    cargo_paths: Vec<String>,
+    /// The files `cargo` may create or change that were in the project directory before it ran.
+    cargo_existing: BTreeSet<String>,
+    /// The staged files that were in the project directory before it was created.
+    existing: BTreeSet<String>,
+    /// How to undo creating the project.
+    rollbacks: Vec<Rollback>,
+    /// The diagnostics of the `cargo` runs.
+    diagnostics: String,
+    /// The version chosen for each dependency.
+    resolved: Vec<(DepTable, String, Choice)>,
+}
+
+impl Generator {
+    /// Create a generator for the given project, loading its templates and finding the enclosing
+    /// workspace.
+    pub fn new(spec: ProjectSpec) -> Result<Generator> {
+        // `cargo` names the package after the directory holding it.
+        let full_path = env::current_dir()?.join(&spec.path);
+        let full_path = fs::canonicalize(&full_path).unwrap_or(full_path);
+        let dir_name = full_path
+            .file_name()
+            .and_then(|dir_name| dir_name.to_str())
+            .ok_or(ErrorKind::InvalidPath)?
+            .to_string();
+        let name = spec.name.clone().unwrap_or_else(|| dir_name.clone());
+
+        let rust_version = match spec.rust_version {
+            Some(ref rust_version) => Some((rust_version, parse_rust_version(rust_version)?)),
+            None => None,
+        };
+
+        // `cargo new` defaults to the newest edition, which an older Rust version may not support.
+        let edition = spec.edition.or_else(|| {
+            rust_version
+                .as_ref()
+                .map(|(_, version)| Edition::newest_for(version))
+        });
+        if let (Some(edition), Some((rust_version, version))) = (edition, rust_version.as_ref()) {
+            if edition.rust_version() > *version {
+                return Err(ErrorKind::IncompatibleEdition(
+                    edition.to_string(),
+                    rust_version.to_string(),
+                )
+                .into());
+            }
+        }
+
+        // A dry run creates the project in a temporary directory, which is removed afterwards.
+        let temp_dir = if spec.dry_run {
+            Some(TempDir::new()?)
+        } else {
+            None
+        };
+        let project_path = match temp_dir {
+            Some(ref temp_dir) => temp_dir.path().join(&dir_name),
+            None => spec.path.clone(),
+        };
+
+        let mut templates = Templates::new(
+            &name,
+            spec.arg_parser,
+            spec.errors,
+            &spec.licenses,
+            spec.readme,
+            spec.query,
+        );
+        templates.set_layout(spec.layout);
+        templates.set_offline(spec.offline);
+        templates.set_allow_prerelease(spec.allow_prerelease);
+        if let Some((_, ref version)) = rust_version {
+            templates.set_rust_version(version.clone());
+        }
+        if spec.query {
+            templates.set_registry(Registry::open(spec.registry.as_deref())?);
+        }
+        if let Some(ref template_dir) = spec.template_dir {
+            templates.load_dir(template_dir)?;
+        }
+        for (key, value) in &spec.vars {
+            templates.set_var(key, value);
+        }
+
+        // The workspace is looked up where the package goes, even when a dry run creates it
+        // elsewhere.
+        let workspace = Workspace::find(&full_path)?;
+        if spec.layout == Layout::Split {
+            if templates.has_pack() {
+                return Err(ErrorKind::SplitTemplatePack.into());
+            }
+            if let Some(ref workspace) = workspace {
+                let manifest_path = workspace.manifest_path().display().to_string();
+                return Err(ErrorKind::SplitWorkspace(manifest_path).into());
+            }
+        }
+
+        // Only the files there before `cargo init` ran are left to the existing file action, so
+        // note which of the files it may create are there already.
+        let package_dirs = match spec.layout {
+            Layout::Default => vec![String::new()],
+            Layout::Split => vec![format!("{}/", name), format!("{}-core/", name)],
+        };
+        let cargo_paths: Vec<String> = package_dirs
+            .iter()
+            .flat_map(|dir| {
+                CARGO_FILES
+                    .iter()
+                    .map(move |file| format!("{}{}", dir, file))
+            })
+            .collect();
+        let cargo_existing = if spec.init {
+            existing_files(&spec.path, &cargo_paths)
+        } else {
+            BTreeSet::new()
+        };
+
+        Ok(Generator {
+            spec,
+            name,
+            project_path,
+            _temp_dir: temp_dir,
+            edition,
+            templates,
+            file_generators: Vec::new(),
+            workspace,
+            member_added: false,
+            cargo_paths,
+            cargo_existing,
+            existing: BTreeSet::new(),
+            rollbacks: Vec::new(),
+            diagnostics: String::new(),
+            resolved: Vec::new(),
+        })
+    }
+
+    /// Register a generator for an additional file, which is staged after the built-in ones.
+    pub fn register<G: FileGenerator + 'static>(&mut self, file_generator: G) {
+        self.file_generators.push(Box::new(file_generator));
+    }
+
+    /// Get the `name` value.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Get the `existing` value, the staged files that were in the project directory before
+    /// `cargo init` ran, relative to it.
+    pub fn existing(&self) -> &BTreeSet<String> {
+        &self.existing
+    }
+
+    /// Get the `diagnostics` value, what `cargo` reported while creating the project.
+    pub fn diagnostics(&self) -> &str {
+        &self.diagnostics
+    }
+
+    /// Get the `resolved` value, the version chosen for each dependency, by dependency table.
+    pub fn resolved(&self) -> &[(DepTable, String, Choice)] {
+        &self.resolved
+    }
+
+    /// Get the path of the project within the enclosing workspace, if it was added to the
+    /// workspace `members`.
+    pub fn member_added(&self) -> Option<&str> {
+        match self.workspace {
+            Some(ref workspace) if self.member_added => Some(workspace.member()),
+            _ => None,
+        }
+    }
+
+    /// Create the project with `cargo new` (or `cargo init`), or natively, preparing to undo it
+    /// unless this is a dry run.
+    pub fn create(&mut self) -> Result<()> {
+        if !self.spec.dry_run {
+            let mut rollback_paths = vec![self.spec.path.clone()];
+            if self.spec.layout == Layout::Split {
+                rollback_paths.push(self.spec.path.join(&self.name));
+                rollback_paths.push(self.spec.path.join(self.core_name()));
+            }
+            self.rollbacks = rollback_paths
+                .iter()
+                .map(|rollback_path| Rollback::prepare(rollback_path))
+                .collect::<Result<Vec<_>>>()?;
+        }
+
+        let name = self.name.clone();
+        match self.spec.layout {
+            Layout::Default => {
+                let diagnostics =
+                    self.create_package(&self.project_path, &name, false, self.spec.vcs)?;
+                self.diagnostics.push_str(&diagnostics);
+            }
+            Layout::Split => {
+                // The repository is kept at the workspace root, rather than in either package.
+                fs::create_dir_all(&self.project_path)?;
+                init_vcs(&self.project_path, self.spec.vcs)?;
+
+                let core_name = self.core_name();
+                for &(ref name, lib) in &[(name, false), (core_name, true)] {
+                    let package_path = self.project_path.join(name);
+                    let diagnostics = self.create_package(&package_path, name, lib, Vcs::None)?;
+                    self.diagnostics.push_str(&diagnostics);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Render everything generated on top of the created project into a stage, which is only
+    /// committed once all of it has rendered.
+    pub fn generate(&mut self) -> Result<Stage> {
+        let stage = match self.spec.layout {
+            Layout::Default => self.generate_package(),
+            Layout::Split => self.generate_split(),
+        }?;
+
+        if self.spec.init {
+            let staged: Vec<&str> = stage.files().into_iter().map(|(path, _)| path).collect();
+            self.existing = existing_files(&self.spec.path, &staged)
+                .into_iter()
+                .filter(|path| {
+                    !self.cargo_paths.contains(path) || self.cargo_existing.contains(path)
+                })
+                .collect();
+        }
+
+        Ok(stage)
+    }
+
+    /// Undo creating the project, removing what was created and restoring any file that was
+    /// changed.  Returns whether there was anything to undo.
+    pub fn rollback(&mut self) -> Result<bool> {
+        let rolled_back = !self.rollbacks.is_empty();
+        for rollback in self.rollbacks.drain(..) {
+            rollback.run()?;
+        }
+        Ok(rolled_back)
+    }
+
+    /// Get the name of the `-core` library package of the split layout.
+    fn core_name(&self) -> String {
+        format!("{}-core", self.name)
+    }
+
+    /// Get the `[package]` values given explicitly.
+    fn package_values(&self) -> Vec<(&'static str, String)> {
+        let mut package_values = Vec::new();
+        if let Some(edition) = self.edition {
+            package_values.push(("edition", edition.to_string()));
+        }
+        if let Some(ref rust_version) = self.spec.rust_version {
+            package_values.push(("rust-version", rust_version.clone()));
+        }
+        package_values
+    }
+
+    /// Create a package at the given path, returning the diagnostics of `cargo`.
+    fn create_package(&self, path: &Path, name: &str, lib: bool, vcs: Vcs) -> Result<String> {
+        if self.spec.native {
+            let mut package = Package::new(path, name);
+            package.set_lib(lib);
+            if let Some(edition) = self.edition {
+                package.set_edition(edition);
+            }
+            package.set_vcs(vcs);
+            if let Some(ref registry) = self.spec.registry {
+                package.set_registry(registry);
+            }
+
+            if self.spec.init {
+                package.init()?;
+            } else {
+                package.create()?;
+            }
+            return Ok(String::new());
+        }
+
+        let edition = self.edition.map(|edition| edition.to_string());
+        let vcs = vcs.to_string();
+        let mut args = vec![
+            if self.spec.init { "init" } else { "new" },
+            if lib { "--lib" } else { "--bin" },
+        ];
+        args.extend(self.spec.cargo_args.iter().map(String::as_str));
+        if self.spec.offline {
+            args.push("--offline");
+        }
+        if let Some(ref registry) = self.spec.registry {
+            args.push("--registry");
+            args.push(registry);
+        }
+        if let Some(ref edition) = edition {
+            args.push("--edition");
+            args.push(edition);
+        }
+        args.extend(&["--vcs", &vcs, "--name", name]);
+        args.push(path.to_str().ok_or(ErrorKind::InvalidPath)?);
+        run_cargo(&args)
+    }
+
+    /// Render the single package of the default layout.
+    fn generate_package(&mut self) -> Result<Stage> {
+        let package_values = self.package_values();
+        let template = &mut self.templates;
+        let workspace = &mut self.workspace;
+        let mut stage = Stage::new(&self.project_path);
+        let mut manifest = Manifest::open(&self.project_path.join("Cargo.toml"))?;
+
+        for &(key, ref val) in &package_values {
+            manifest.set_package_value(key, val)?;
+        }
+        if let Some(ref workspace) = *workspace {
+            // The keys given explicitly aren't inherited from the workspace.
+            for key in &INHERITABLE_KEYS {
+                if inherits(workspace, key, template.licenses())
+                    && !package_values.iter().any(|&(given, _)| given == *key)
+                {
+                    manifest.inherit_package_value(key)?;
+                }
+            }
+        }
+        // A manifest without an edition is a 2015 edition package.
+        let edition = manifest
+            .package_value("edition")
+            .or_else(|| {
+                workspace
+                    .as_ref()
+                    .and_then(|ws| ws.package_value("edition"))
+            })
+            .unwrap_or("2015");
+        template.set_edition(edition.parse()?);
+
+        // A template pack replaces the built-in files, except the license texts, which follow
+        // `--license` unless the pack supplies its own.
+        let has_pack = template.has_pack();
+        let builtin: Vec<Box<dyn FileGenerator>> = if has_pack {
+            for (file_path, contents) in template.pack_files()? {
+                stage.replace(&file_path, contents);
+            }
+            output::licenses(template)
+        } else {
+            output::builtin(template, Layout::Default)
+        };
+
+        let mut patches = Vec::new();
+        for generator in builtin.iter().chain(&self.file_generators) {
+            if let Some(patch) = stage_file(&mut stage, "", &**generator, template, has_pack)? {
+                patches.push(patch);
+            }
+        }
+        if has_pack && self.spec.readme && stage.exists(template.cargo_toml_readme()) {
+            patches
+                .push(ManifestPatch::new().package_value("readme", template.cargo_toml_readme()));
+        }
+
+        let mut deps = BTreeMap::new();
+        self.resolved = template.add_deps(&mut deps);
+        for patch in &patches {
+            merge_dependencies(&mut deps, patch);
+            for (key, val) in patch.package_values() {
+                let inherited = workspace
+                    .as_ref()
+                    .is_some_and(|workspace| inherits(workspace, key, template.licenses()));
+                if !inherited {
+                    manifest.set_package_value(key, val)?;
+                }
+            }
+            add_lints(&mut manifest, patch, workspace.as_mut())?;
+        }
+        add_dependencies(&mut manifest, deps, workspace.as_mut())?;
+        stage.replace("Cargo.toml", manifest.to_string());
+
+        if let Some(ref mut workspace) = *workspace {
+            self.member_added = workspace.add_member()?;
+            let manifest_path = workspace.manifest_path().to_string_lossy().into_owned();
+            stage.replace(&manifest_path, workspace.manifest());
+        }
+
+        Ok(stage)
+    }
+
+    /// Render the split layout on top of its two packages: the workspace manifest, licenses and
+    /// README.md at the root, and the binary and `-core` library packages, which inherit their
+    /// shared `[package]` keys from the workspace.
+    fn generate_split(&mut self) -> Result<Stage> {
+        let name = self.name.clone();
+        let core_name = self.core_name();
+        let package_values = self.package_values();
+        let template = &mut self.templates;
+        let root = &self.project_path;
+        let mut stage = Stage::new(root);
+        let mut manifest = Manifest::open(&root.join(&name).join("Cargo.toml"))?;
+        let mut core_manifest = Manifest::open(&root.join(&core_name).join("Cargo.toml"))?;
+
+        // A manifest without an edition is a 2015 edition package.
+        let edition = manifest
+            .package_value("edition")
+            .unwrap_or("2015")
+            .parse::<Edition>()?;
+        template.set_edition(edition);
+        let version = manifest.package_value("version").unwrap_or("0.1.0");
+
+        let mut shared = vec![
+            ("version".to_string(), version.to_string()),
+            ("edition".to_string(), edition.to_string()),
+        ];
+        for (key, val) in package_values {
+            shared.retain(|(shared_key, _)| shared_key != key);
+            shared.push((key.to_string(), val));
+        }
+
+        let mut deps = BTreeMap::new();
+        self.resolved = template.add_deps(&mut deps);
+        let mut core_deps = BTreeMap::new();
+        self.resolved.extend(template.add_core_deps(&mut core_deps));
+
+        // The dependencies that come with the files at the root go to the binary package.
+        let builtin = output::builtin(template, Layout::Split);
+        for generator in builtin.iter().chain(&self.file_generators) {
+            let dir = match generator.destination() {
+                Destination::Root => String::new(),
+                Destination::Binary => format!("{}/", name),
+                Destination::Library => format!("{}/", core_name),
+            };
+            let patch = match stage_file(&mut stage, &dir, &**generator, template, false)? {
+                Some(patch) => patch,
+                None => continue,
+            };
+
+            match generator.destination() {
+                Destination::Root => {
+                    merge_dependencies(&mut deps, &patch);
+                    add_lints(&mut manifest, &patch, None)?;
+                    for (key, val) in patch.package_values() {
+                        shared.retain(|(shared_key, _)| shared_key != key);
+                        shared.push((key.clone(), val.clone()));
+                    }
+                }
+                Destination::Binary => {
+                    merge_dependencies(&mut deps, &patch);
+                    add_lints(&mut manifest, &patch, None)?;
+                    for (key, val) in patch.package_values() {
+                        manifest.set_package_value(key, val)?;
+                    }
+                }
+                Destination::Library => {
+                    merge_dependencies(&mut core_deps, &patch);
+                    add_lints(&mut core_manifest, &patch, None)?;
+                    for (key, val) in patch.package_values() {
+                        core_manifest.set_package_value(key, val)?;
+                    }
+                }
+            }
+        }
+
+        add_dependencies(&mut manifest, deps, None)?;
+        add_dependencies(&mut core_manifest, core_deps, None)?;
+
+        let mut workspace = Manifest::workspace(&[&name, &core_name], edition.resolver());
+        for (key, val) in &shared {
+            workspace.set_workspace_package_value(key, val)?;
+            manifest.inherit_package_value(key)?;
+            core_manifest.inherit_package_value(key)?;
+        }
+
+        stage.create("Cargo.toml", workspace.to_string());
+        stage.replace(&format!("{}/Cargo.toml", name), manifest.to_string());
+        stage.replace(
+            &format!("{}/Cargo.toml", core_name),
+            core_manifest.to_string(),
+        );
+        Ok(stage)
+    }
+}
+
+/// Stage the file of the given generator, if it produces one, under the given directory.  Returns
+/// the manifest patch that comes with the file.  When `keep_staged` is set, a file that's already
+/// there is kept, and only the manifest patch is taken.
+fn stage_file(
+    stage: &mut Stage,
+    dir: &str,
+    generator: &dyn FileGenerator,
+    template: &Templates,
+    keep_staged: bool,
+) -> Result<Option<ManifestPatch>> {
+    let contents = match generator.contents(template)? {
+        Some(contents) => contents,
+        None => return Ok(None),
+    };
+
+    let file_path = format!("{}{}", dir, generator.path());
+    if keep_staged && stage.exists(&file_path) {
+        // The file the template pack supplies is kept.
+    } else if generator.replaces() {
+        stage.replace(&file_path, contents);
+    } else {
+        stage.create(&file_path, contents);
+    }
+    Ok(Some(generator.manifest_patch(template)))
+}
+
+/// Does the package inherit the given `[package]` key from the workspace?  The `license` is only
+/// inherited when the workspace expression names the selected licenses, as the license files and
+/// headers follow those.
+fn inherits(workspace: &Workspace, key: &str, licenses: &[License]) -> bool {
+    if !workspace.defines(key) {
+        return false;
+    } else if key != "license" || licenses.is_empty() {
+        return true;
+    }
+
+    workspace
+        .package_value(key)
+        .and_then(|expression| parse_licenses(expression).ok())
+        .is_some_and(|inherited| {
+            inherited.len() == licenses.len()
+                && inherited.iter().all(|license| licenses.contains(license))
+        })
+}
+
+/// Merge the dependencies of the manifest patch into the given ones, which take precedence.
+fn merge_dependencies(
+    deps: &mut BTreeMap<DepTable, BTreeMap<String, Dependency>>,
+    patch: &ManifestPatch,
+) {
+    for (table, table_deps) in patch.dependencies() {
+        let merged = deps.entry(table.clone()).or_default();
+        for (name, dep) in table_deps {
+            merged.entry(name.clone()).or_insert_with(|| dep.clone());
+        }
+    }
+}
+
+/// Add the dependencies to the manifest.  Registry dependencies are added to the workspace instead
+/// when it shares its dependencies, and inherited from there.
+fn add_dependencies(
+    manifest: &mut Manifest,
+    mut deps: BTreeMap<DepTable, BTreeMap<String, Dependency>>,
+    mut workspace: Option<&mut Workspace>,
+) -> Result<()> {
+    for (table, table_deps) in &mut deps {
+        if let Some(ref mut workspace) = workspace {
+            if workspace.has_dependencies() {
+                for (name, dep) in table_deps.iter_mut() {
+                    if dep.is_registry() {
+                        *dep = workspace.add_dependency(name, dep)?;
+                    }
+                }
+            }
+        }
+        manifest.add_dependencies(table, table_deps)?;
+    }
+
+    Ok(())
+}
+
+/// Add the lints of the manifest patch to the manifest, or to the workspace when the manifest
+/// inherits its lints from there.
+fn add_lints(
+    manifest: &mut Manifest,
+    patch: &ManifestPatch,
+    mut workspace: Option<&mut Workspace>,
+) -> Result<()> {
+    for (tool, lints) in patch.lints() {
+        match workspace {
+            Some(ref mut workspace) if manifest.inherits_lints() => {
+                workspace.add_lints(tool, lints)?
+            }
+            _ => manifest.add_lints(tool, lints)?,
+        }
+    }
+
+    Ok(())
+}
+
+/// Run `cargo` with the given arguments, returning its diagnostics.  They are part of the error
+/// when it fails.
+fn run_cargo(args: &[&str]) -> Result<String> {
+    let output = Command::new("cargo")
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .args(args)
+        .output()?;
+
+    let stderr = String::from_utf8_lossy(&output.stderr).into_owned();
+    if output.status.success() {
+        Ok(stderr)
+    } else {
+        Err(ErrorKind::CargoCommand(
+            format!("cargo {}", args.join(" ")),
+            output.status.to_string(),
+            stderr.trim_end().to_string(),
+        )
+        .into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{run_cargo, Generator, ProjectSpec};
+    use error::{ErrorKind, Result};
+    use license::License;
+    use manifest::{DepKind, DepTable, Dependency, ManifestPatch};
+    use output::FileGenerator;
+    use package::Vcs;
+    use std::fs;
+    use tempfile::TempDir;
+    use tmpl::{ArgParser, Edition, ErrorCrate, Layout, Templates};
+    use util::read_to_string;
+
+    /// A generator of an integration test, which needs a dev-dependency.
+    struct CliTest;
+
+    impl FileGenerator for CliTest {
+        fn path(&self) -> &str {
+            "tests/cli.rs"
+        }
+
+        fn contents(&self, templates: &Templates) -> Result<Option<String>> {
+            Ok(Some(format!("// {}\n", templates.cargo_toml_readme())))
+        }
+
+        fn manifest_patch(&self, _templates: &Templates) -> ManifestPatch {
+            let dev = DepTable::new(DepKind::Dev);
+            ManifestPatch::new().dependency(dev, "assert_cmd", Dependency::new("2.0.0"))
+        }
+    }
+
+    #[test]
+    fn split_layout_links_the_binary_to_the_core_library() {
+        let dir = TempDir::new().expect("Unable to create the directory!");
+        let root = dir.path().join("demo");
+        let spec = ProjectSpec::new(&root)
+            .layout(Layout::Split)
+            .native(true)
+            .vcs(Vcs::None)
+            .query(false);
+        let mut generator = Generator::new(spec).expect("Unable to create the generator!");
+        generator.create().expect("Unable to create the packages!");
+        generator
+            .generate()
+            .expect("Unable to generate the project!")
+            .commit()
+            .expect("Unable to write the project!");
+
+        let read = |path: &str| read_to_string(&root.join(path)).expect("Unable to read!");
+        let workspace = read("Cargo.toml");
+        assert!(workspace.contains("members = [\"demo\", \"demo-core\"]"));
+        assert!(workspace.contains("[workspace.package]\nversion = \"0.1.0\"\n"));
+
+        let binary = read("demo/Cargo.toml");
+        assert!(binary.contains("name = \"demo\"\n"));
+        assert!(binary.contains("version.workspace = true\n"));
+        assert!(binary.contains("demo-core = { version = \"0.1.0\", path = \"../demo-core\" }"));
+        assert!(!binary.contains("error-chain"));
+        assert!(read("demo/src/run.rs").contains("use demo_core::{greet, Result};"));
+        assert!(!root.join("demo/src/lib.rs").exists());
+        assert!(!root.join("demo/src/error.rs").exists());
+
+        let core = read("demo-core/Cargo.toml");
+        assert!(core.contains("name = \"demo-core\"\n"));
+        assert!(core.contains("error-chain = "));
+        assert!(!core.contains("clap"));
+        assert!(read("demo-core/src/lib.rs").contains("pub fn greet(name: &str) -> Result<()>"));
+        assert!(read("demo-core/src/error.rs").contains("error_chain!"));
+        assert!(!root.join("demo-core/src/main.rs").exists());
+
+        let mut entries = fs::read_dir(&root)
+            .expect("Unable to read the project!")
+            .map(|entry| entry.expect("Unable to read the entry!").file_name())
+            .collect::<Vec<_>>();
+        entries.sort();
+        assert_eq!(
+            entries,
+            [
+                "Cargo.toml",
+                "LICENSE-APACHE",
+                "LICENSE-MIT",
+                "README.md",
+                "demo",
+                "demo-core"
+            ]
+        );
+    }
+
+    #[test]
+    fn failing_cargo_commands_report_their_output() {
+        let dir = TempDir::new().expect("Unable to create the directory!");
+        let path = dir.path().to_str().expect("The path isn't UTF-8!");
+        let err = run_cargo(&["new", "--vcs", "none", path]).unwrap_err();
+
+        match *err.kind() {
+            ErrorKind::CargoCommand(ref command, ref status, ref output) => {
+                assert_eq!(*command, format!("cargo new --vcs none {}", path));
+                assert_eq!(status, "exit status: 101");
+                assert!(output.contains("already exists"), "{}", output);
+            }
+            ref kind => panic!("unexpected error: {}", kind),
+        }
+    }
+
+    #[test]
+    fn project_specs_drive_the_staged_files() {
+        let dir = TempDir::new().expect("Unable to create the directory!");
+        let root = dir.path().join("flambe");
+        let spec = ProjectSpec::new(&root)
+            .arg_parser(ArgParser::Lexopt)
+            .errors(ErrorCrate::Anyhow)
+            .licenses(&[License::Mpl])
+            .readme(false)
+            .edition(Edition::Edition2021)
+            .native(true)
+            .vcs(Vcs::None)
+            .query(false);
+        let mut generator = Generator::new(spec).expect("Unable to create the generator!");
+        assert_eq!(generator.name(), "flambe");
+        generator.create().expect("Unable to create the package!");
+        let stage = generator
+            .generate()
+            .expect("Unable to generate the project!");
+
+        let files = stage.files();
+        let paths = files.iter().map(|&(path, _)| path).collect::<Vec<_>>();
+        assert_eq!(
+            paths,
+            [
+                "src/main.rs",
+                "src/error.rs",
+                "src/run.rs",
+                "LICENSE-MPL-2.0",
+                "Cargo.toml"
+            ]
+        );
+        let contents = |path: &str| {
+            files
+                .iter()
+                .find(|&&(file_path, _)| file_path == path)
+                .map(|&(_, contents)| contents)
+                .expect("The file isn't staged!")
+        };
+        assert!(contents("src/error.rs").contains("pub use anyhow::Result;"));
+        assert!(contents("src/run.rs").contains("use crate::error::Result;"));
+        let manifest = contents("Cargo.toml");
+        assert!(manifest.contains("edition = \"2021\"\n"));
+        assert!(manifest.contains("license = \"MPL-2.0\"\n"));
+        assert!(manifest.contains("anyhow = \"1.0.0\"\nlexopt = \"0.3.0\"\n"));
+        assert!(!manifest.contains("readme"));
+
+        let resolved = generator
+            .resolved()
+            .iter()
+            .map(|(_, name, choice)| (name.as_str(), choice.version()))
+            .collect::<Vec<_>>();
+        assert_eq!(resolved, [("anyhow", "1.0.0"), ("lexopt", "0.3.0")]);
+
+        // Nothing but the package `cargo new` would create is written before the commit.
+        assert!(!root.join("src/run.rs").exists());
+        stage.commit().expect("Unable to write the project!");
+        assert!(root.join("src/run.rs").is_file());
+    }
+
+    #[test]
+    fn registered_generators_are_staged_after_the_builtin_ones() {
+        let dir = TempDir::new().expect("Unable to create the directory!");
+        let spec = ProjectSpec::new(&dir.path().join("demo"))
+            .native(true)
+            .vcs(Vcs::None)
+            .query(false);
+        let mut generator = Generator::new(spec).expect("Unable to create the generator!");
+        generator.register(CliTest);
+        generator.create().expect("Unable to create the package!");
+        let stage = generator
+            .generate()
+            .expect("Unable to generate the project!");
+
+        let files = stage.files();
+        let paths = files.iter().map(|&(path, _)| path).collect::<Vec<_>>();
+        assert_eq!(
+            paths[paths.len() - 3..],
+            ["README.md", "tests/cli.rs", "Cargo.toml"]
+        );
+        assert_eq!(files[paths.len() - 2].1, "// README.md\n");
+        assert!(files[paths.len() - 1]
+            .1
+            .contains("[dev-dependencies]\nassert_cmd = \"2.0.0\"\n"));
+    }
+}
@@ -0,0 +1,428 @@
+// Copyright (c) 2017 cargo-cli developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Create a command line interface binary with some common dependencies (([`clap`][clap] ||
+//! [`docopt`][docopt]) and [`error_chain`][error_chain])
+//!
+//! # Installation
+//! `cargo install cargo-cli`
+//!
+//! # Usage
+//! In general, this is extension is used in the same manner as you would use `cargo new --bin`.
+//! Most of the command line arguments supported by `cargo new` are supported by `cargo cli` and are
+//! actually passed through to `cargo new`.
+//!
+//! The generated files are rendered in full before any of them are written, and should anything
+//! fail after `cargo new` has run, the project it created is removed again.  The diagnostics of
+//! `cargo new` are shown when it fails, or always with `-v`.
+//!
+//! In addition, `cargo cli` supports the following options:
+//!
+//! * `arg_parser`: Specify the argument parser to use in the generated output. [default: clap]
+//!   [values: clap, clap-derive, docopt, lexopt, pico-args, argh, bpaf]
+//! * `errors`: Specify the error handling crate to use in the generated output.  With
+//!   error-chain, `Cargo.toml` declares the cfg its macro tests under `[lints.rust]` (or
+//!   `[workspace.lints.rust]` when the package inherits its lints), so the project builds
+//!   without `unexpected_cfgs` warnings. [default: error-chain]
+//!   [values: error-chain, anyhow, thiserror, snafu, eyre, std]
+//! * `edition`: Set the Rust edition of the generated output, which is passed through to
+//!   `cargo new` and selects edition-appropriate templates. [values: 2015, 2018, 2021, 2024]
+//! * `rust-version`: Set the minimum supported Rust version of the generated output.  Each
+//!   dependency gets its newest release whose declared `rust-version` is compatible, and the
+//!   edition defaults to the newest one that Rust version supports.
+//! * `layout`: Choose the project layout.  `split` creates a workspace holding a `<name>` binary
+//!   package for the argument parsing and a `<name>-core` library package for the logic and error
+//!   types, which the binary depends on by path. [default: default]  [values: default, split]
+//! * `license`: Specify licensing to include in the generated output, as an SPDX identifier or
+//!   several joined by `OR` to let users choose.  Each license text is included as a
+//!   `LICENSE-<ID>` file, the source files start with the notice the license calls for, and
+//!   `Cargo.toml` gets the `license` expression. [default: both]  [values: MIT, Apache-2.0,
+//!   BSD-2-Clause, BSD-3-Clause, ISC, MPL-2.0, GPL-3.0-or-later, LGPL-2.1-or-later,
+//!   LGPL-3.0-or-later, AGPL-3.0-or-later, Unlicense, Zlib, 0BSD, both (MIT OR Apache-2.0), none]
+//! * `template-dir`: Load templates from a directory mirroring the generated layout, falling back
+//!   to the built-in templates for any missing files.  If the directory holds a `template.toml`,
+//!   it is loaded as a template pack instead.
+//! * `template`: Use the installed template pack with the given name.
+//! * `var`: Set a template variable used by a template pack (`KEY=VALUE`, may be repeated).
+//! * `no-readme`: Turn off README.md generation.
+//! * `dry-run`: Render the templates and resolve the versions without writing anything, printing
+//!   the file tree, the size of each generated file (its contents with `-v`) and a diff of the
+//!   `Cargo.toml` changes instead.
+//! * `allow-prerelease`: Allow pre-release versions when querying for the latest version.  By
+//!   default the newest stable, non-yanked release compatible with the version the templates are
+//!   written for (e.g. clap 2 for `clap`) is chosen, and `-v` reports why.
+//! * `no-latest`: Turn off the crates.io query for the latest version (use defaults).
+//! * `native`: Create the package directory, `Cargo.toml`, `src/main.rs` and version control setup
+//!   without running `cargo new`, for when `cargo` is not on `PATH`.  `--vcs` and `--name` are
+//!   honored, names `cargo new` refuses, such as keywords, `std` or `test`, are rejected, and any
+//!   failure is reported in full.
+//! * `offline`: Run without accessing the network.  The latest versions are resolved from the
+//!   crates downloaded to the local registry cache under `CARGO_HOME` instead of crates.io, so the
+//!   generated package builds offline.  This is also the fallback whenever crates.io can't be
+//!   reached.
+//! * `registry`: Look the latest versions up in an alternate registry configured in
+//!   `.cargo/config.toml`, and take the dependencies from it.
+//!
+//! ```text
+//! cargo-cli 0.1.0
+//!
+//! USAGE:
+//!     cargo-cli cli [FLAGS] [OPTIONS] <path>
+//!
+//! FLAGS:
+//!         --allow-prerelease    Allow pre-release versions when querying for the latest version.
+//!         --dry-run             Render the templates and resolve the versions, but only print the
+//!                               files and Cargo.toml changes instead of writing them.
+//!         --frozen              Require Cargo.lock and cache are up to date
+//!     -h, --help                Prints help information
+//!         --locked              Require Cargo.lock is up to date
+//!         --native              Create the package without running cargo, for when it isn't
+//!                               available.
+//!         --no-latest           Turn off the crates.io query for the latest version (use
+//!                               defaults).
+//!         --no-readme           Turn off README.md generation.
+//!         --offline             Run without accessing the network, resolving the latest versions
+//!                               from the local registry cache
+//!     -q, --quiet               No output printed to stdout
+//!     -v                        Use verbose output (-vv very verbose/build.rs output)
+//!
+//! OPTIONS:
+//!     -a, --arg_parser <PARSER>    Specify the argument parser to use in the generated output.
+//!                                  [default: clap]  [values: clap, clap-derive,
+//!                                  docopt, lexopt, pico-args, argh, bpaf]
+//!         --color <WHEN>           Coloring [default: auto]  [values: auto, always, never]
+//!         --edition <YEAR>         Set the Rust edition of the generated output.
+//!                                  [values: 2015, 2018, 2021, 2024]
+//!         --errors <CRATE>         Specify the error handling crate to use in the generated
+//!                                  output. [default: error-chain]  [values: error-chain,
+//!                                  anyhow, thiserror, snafu, eyre, std]
+//!         --layout <LAYOUT>        Choose the project layout, split being a workspace with a
+//!                                  binary package and a -core library package.
+//!                                  [default: default]  [values: default, split]
+//!         --license <SPDX>         Specify licensing to include in the generated output, as
+//!                                  SPDX identifiers joined by OR, both (MIT OR Apache-2.0) or
+//!                                  none. [default: both]
+//!         --name <NAME>            Set the resulting package name, defaults to the value of
+//!                                  <path>.
+//!         --registry <REGISTRY>    Look the latest versions up in the given alternate registry
+//!                                  from .cargo/config.toml, and depend on it.
+//!         --rust-version <X.Y>     Set the minimum supported Rust version of the generated
+//!                                  output, choosing dependency versions compatible with it.
+//!         --template-dir <DIR>     Load templates from a directory mirroring the generated
+//!                                  layout, falling back to the built-in templates for any
+//!                                  missing files.
+//!         --template <NAME>        Use the installed template pack with the given name.
+//!         --var <KEY=VALUE>...     Set a template variable used by a template pack.
+//!         --vcs <VCS>              Initialize a new repository for the given version control
+//!                                  system or do not initialize any version control at all,
+//!                                  overriding a global configuration. [default: git]
+//!                                  [values: git, hg, pijul, fossil, none]
+//!
+//! ARGS:
+//!     <path>
+//! ```
+//!
+//! # Examples
+//! ### With clap
+//! `cargo cli <path>`
+//!
+//! ### With the clap derive API
+//! `cargo cli -a clap-derive <path>`
+//!
+//! ### With docopt
+//! `cargo cli -a docopt <path>`
+//!
+//! ### With a lightweight argument parser
+//! `cargo cli -a lexopt <path>`
+//!
+//! ### With a different error handling crate
+//! `cargo cli --errors anyhow <path>`
+//!
+//! ### No licenses or README.md
+//! `cargo cli --license none --no-readme <path>`
+//!
+//! ### Under a copyleft license
+//! `cargo cli --license GPL-3.0-or-later <path>`
+//!
+//! ### As a binary and a library package
+//! `cargo cli --layout split <path>`
+//!
+//! ### With a template directory
+//! `cargo cli --template-dir ~/.cargo-cli/templates <path>`
+//!
+//! ### Review a template pack without writing anything
+//! `cargo cli --dry-run -v --template <NAME> <path>`
+//!
+//! ### With some `cargo new` arguments
+//! `cargo cli --vcs pijul -vv -a docopt --edition 2021 --name flambe <path>`
+//!
+//! # Existing Directories
+//! `cargo cli init` generates the application in an existing directory, such as a freshly cloned
+//! repository, by wrapping `cargo init`.  It takes the same options as `cargo cli`, with `<path>`
+//! defaulting to the current directory.  When a generated file is already there, `--existing`
+//! chooses whether to `skip` it, `overwrite` it or `merge` both versions with conflict markers to
+//! resolve by hand, which is refused for a `Cargo.toml` as the markers would leave it invalid.  By
+//! default it asks about each such file, and skips them when there's no terminal to ask on.
+//!
+//! ```text
+//! cargo cli init [--existing <ACTION>] [OPTIONS] [path]
+//! ```
+//!
+//! # Workspaces
+//! A project generated inside a Cargo workspace is added to its `members`, unless a `members` glob
+//! already covers it or the workspace `exclude`s it.  The `[package]` keys the workspace defines in
+//! `[workspace.package]` are inherited (`edition.workspace = true`) rather than set, except the
+//! ones given explicitly such as `--edition`, and the `license` when it names other licenses than
+//! the ones the license files and headers are generated for.  When the workspace has a
+//! `[workspace.dependencies]` table, the registry dependencies are added there, unless already
+//! present, and the project inherits them with `{ workspace = true }`.
+//!
+//! # Template Packs
+//! A template pack is a directory holding a `template.toml` manifest and the mustache templates
+//! it references.  The manifest lists every file the pack produces, the variables those files
+//! need, the conditions under which they are emitted and the dependencies to add to `Cargo.toml`.
+//!
+//! ```toml
+//! description = "A clap command line application"
+//!
+//! [variables.about]
+//! description = "One line description of the application"
+//! default = "A command line application"
+//!
+//! [[files]]
+//! path = "src/main.rs"
+//! template = "main.rs"
+//! header = true
+//!
+//! [[files]]
+//! path = "README.md"
+//! template = "README.md"
+//! vars = ["about"]
+//! when = "readme"
+//!
+//! [dependencies]
+//! clap = { version = "4.0.0", features = ["derive"] }
+//! anyhow = "1.0.0"
+//!
+//! [dev-dependencies]
+//! assert_cmd = "2.0.0"
+//!
+//! [target.'cfg(unix)'.dependencies]
+//! nix = "0.29.0"
+//! ```
+//!
+//! The variables `name`, `year`, `arg_parser`, `errors`, `edition`, `mit`, `apache`, `license`,
+//! `readme`, `clap` and `docopt` are always available.  A `when` condition is a variable name, a
+//! negated variable name (`!readme`) or a comparison (`arg_parser == clap`,
+//! `arg_parser != docopt`).  The `path` and `template` of a file must be relative and must not
+//! contain `..`, and templates must not be symbolic links out of the pack, so a pack can neither
+//! read nor write outside of its own and the project directory.
+//!
+//! The `[dev-dependencies]`, `[build-dependencies]` and `[target.<platform>]` tables are merged
+//! into the generated `Cargo.toml` like `[dependencies]`.  Dependencies take any of the forms
+//! `Cargo.toml` accepts, including `default-features`, `optional`, `path`, `git` and `package`
+//! renames.  The versions of registry dependencies are replaced with the latest ones matching the
+//! declared version requirement unless `--no-latest` is given, so `clap = "4"` never becomes
+//! clap 5.
+//!
+//! ### Installed Template Packs
+//! Template packs can be installed by name into the user data directory (or `$CARGO_CLI_HOME` when
+//! set) and then selected with `--template <NAME>`.
+//!
+//! ```text
+//! cargo cli templates list
+//! cargo cli templates show <NAME>
+//! cargo cli templates install [--name <NAME>] [--force] <source>
+//! cargo cli templates remove <NAME>
+//! ```
+//!
+//! `<source>` is either a template pack directory or a local git repository holding one.
+//!
+//! # Alternate Registries
+//! The latest dependency versions are looked up on crates.io by default.  A crates.io mirror set up
+//! through `[source]` replacement in `.cargo/config.toml` is used instead when configured, and
+//! `--registry <NAME>` selects one of the `[registries]`, which the generated dependencies are then
+//! taken from.  Registries using the sparse index protocol are queried directly, while those with a
+//! git index are read from the local registry cache.
+//!
+//! ```toml
+//! [registries.internal]
+//! index = "sparse+https://crates.example.com/index/"
+//!
+//! [source.crates-io]
+//! replace-with = "mirror"
+//!
+//! [source.mirror]
+//! registry = "sparse+https://mirror.example.com/index/"
+//! ```
+//!
+//! # Lookup Cache
+//! The releases looked up for each dependency are cached for a day in the user cache directory (or
+//! `$CARGO_CLI_HOME/cache` when set), so repeated runs don't query the registry again.  The lookups
+//! are made concurrently when the cache doesn't have them.
+//!
+//! ```text
+//! cargo cli cache list
+//! cargo cli cache clear [--expired]
+//! ```
+//!
+//! # Library
+//! The scaffolding is also available as the `cargo_cli` library, which the `cargo cli` binary is a
+//! thin front-end over.  A [`ProjectSpec`] describes the project with the same choices as the
+//! command line options, and the [`Generator`] built from it creates the package and renders the
+//! generated files and manifest edits into a [`Stage`](stage::Stage), which can be inspected with
+//! `files()` before it's committed.
+//!
+//! ```no_run
+//! # fn main() -> cargo_cli::error::Result<()> {
+//! use cargo_cli::license::License;
+//! use cargo_cli::tmpl::{ArgParser, ErrorCrate};
+//! use cargo_cli::{Generator, ProjectSpec};
+//! use std::path::Path;
+//!
+//! let spec = ProjectSpec::new(Path::new("flambe"))
+//!     .arg_parser(ArgParser::Lexopt)
+//!     .errors(ErrorCrate::Anyhow)
+//!     .licenses(&[License::Mpl]);
+//! let mut generator = Generator::new(spec)?;
+//! generator.create()?;
+//!
+//! let stage = generator.generate()?;
+//! for (path, contents) in stage.files() {
+//!     println!("{} ({} bytes)", path, contents.len());
+//! }
+//! stage.commit()?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Every generated file comes from a [`FileGenerator`](output::FileGenerator), which gives its
+//! path, the package it belongs to, its contents and the changes to `Cargo.toml` that come with it.
+//! The built-in files are generators too, and [`Generator::register`] adds more, which are staged
+//! after them.
+//!
+//! ```no_run
+//! use cargo_cli::error::Result;
+//! use cargo_cli::manifest::{DepKind, DepTable, Dependency, ManifestPatch};
+//! use cargo_cli::output::FileGenerator;
+//! use cargo_cli::tmpl::Templates;
+//!
+//! struct CliTest;
+//!
+//! impl FileGenerator for CliTest {
+//!     fn path(&self) -> &str {
+//!         "tests/cli.rs"
+//!     }
+//!
+//!     fn contents(&self, _templates: &Templates) -> Result<Option<String>> {
+//!         Ok(Some("#[test]\nfn runs() {}\n".to_string()))
+//!     }
+//!
+//!     fn manifest_patch(&self, _templates: &Templates) -> ManifestPatch {
+//!         let dev = DepTable::new(DepKind::Dev);
+//!         ManifestPatch::new().dependency(dev, "assert_cmd", Dependency::new("2.0.0"))
+//!     }
+//! }
+//!
+//! # fn main() -> Result<()> {
+//! # let spec = cargo_cli::ProjectSpec::new(std::path::Path::new("flambe"));
+//! # let mut generator = cargo_cli::Generator::new(spec)?;
+//! generator.register(CliTest);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! # CLI Layout
+//!
+//! ### Default
+//! ```text
+//! .
+//! ├── Cargo.toml
+//! ├── LICENSE-APACHE
+//! ├── LICENSE-MIT
+//! ├── README.md
+//! └── src
+//!     ├── error.rs
+//!     ├── main.rs
+//!     └── run.rs
+//! ```
+//!
+//! ### Split
+//! ```text
+//! .
+//! ├── Cargo.toml
+//! ├── LICENSE-APACHE
+//! ├── LICENSE-MIT
+//! ├── README.md
+//! ├── <name>
+//! │   ├── Cargo.toml
+//! │   └── src
+//! │       ├── main.rs
+//! │       └── run.rs
+//! └── <name>-core
+//!     ├── Cargo.toml
+//!     └── src
+//!         ├── error.rs
+//!         └── lib.rs
+//! ```
+//!
+//! The workspace `Cargo.toml` defines the version, edition, license and README.md in
+//! `[workspace.package]` for both packages to inherit, and the repository is initialized at the
+//! workspace root.  `lib.rs` re-exports the error types, so `run.rs` uses them from the `-core`
+//! crate.  Template packs can't be used with this layout, nor can it be created inside another
+//! workspace.
+//!
+//! ### No Licenses or README.md
+//! ```text
+//! .
+//! ├── Cargo.toml
+//! └── src
+//!     ├── error.rs
+//!     ├── main.rs
+//!     └── run.rs
+//! ```
+//!
+//! [clap]: https://clap.rs/
+//! [docopt]: https://github.com/docopt/docopt.rs
+//! [error_chain]: https://github.com/brson/error-chain
+//!
+#![deny(missing_docs)]
+#![recursion_limit = "256"]
+#[macro_use]
+extern crate error_chain;
+#[macro_use]
+extern crate serde_derive;
+
+extern crate curl;
+extern crate dirs;
+extern crate mustache;
+extern crate semver;
+extern crate serde_json;
+extern crate similar;
+extern crate tempfile;
+extern crate term;
+extern crate toml;
+extern crate toml_edit;
+
+pub mod cache;
+pub mod error;
+pub mod generator;
+pub mod license;
+pub mod manifest;
+pub mod output;
+pub mod pack;
+pub mod package;
+pub mod registry;
+pub mod stage;
+pub mod store;
+pub mod tmpl;
+pub mod workspace;
+
+mod util;
+
+pub use generator::{Generator, ProjectSpec};
@@ -0,0 +1,469 @@
+// Copyright (c) 2017 cargo-cli developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! `cargo-cli` license catalogue.
+//!
+//! The licenses `--license` accepts, by SPDX identifier, with their bundled texts, the notice
+//! each generated source file starts with and the `license` expression for `Cargo.toml`.
+
+use error::{Error, ErrorKind, Result};
+use std::fmt;
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The width the notices in the source file headers are wrapped to, not counting the `// `.
+const NOTICE_WIDTH: usize = 77;
+
+/// The seconds in a day.
+const SECS_PER_DAY: u64 = 24 * 60 * 60;
+/// The days from 0000-03-01 to the Unix epoch.
+const DAYS_TO_EPOCH: u64 = 719_468;
+/// The days in a 400 year cycle of the Gregorian calendar.
+const DAYS_PER_ERA: u64 = 146_097;
+
+/// The sentence ending the notice of permissive licenses.
+const TERMS: &str =
+    "All files in the project carrying such notice may not be copied, modified, or \
+     distributed except according to those terms.";
+
+/// A license the generated output can be licensed under.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum License {
+    /// MIT
+    Mit,
+    /// Apache-2.0
+    Apache,
+    /// BSD-2-Clause
+    Bsd2Clause,
+    /// BSD-3-Clause
+    Bsd3Clause,
+    /// ISC
+    Isc,
+    /// MPL-2.0
+    Mpl,
+    /// GPL-3.0-or-later
+    Gpl,
+    /// LGPL-2.1-or-later
+    Lgpl21,
+    /// LGPL-3.0-or-later
+    Lgpl3,
+    /// AGPL-3.0-or-later
+    Agpl,
+    /// Unlicense
+    Unlicense,
+    /// Zlib
+    Zlib,
+    /// 0BSD
+    ZeroBsd,
+}
+
+/// Every license in the catalogue.
+pub const LICENSES: [License; 13] = [
+    License::Mit,
+    License::Apache,
+    License::Bsd2Clause,
+    License::Bsd3Clause,
+    License::Isc,
+    License::Mpl,
+    License::Gpl,
+    License::Lgpl21,
+    License::Lgpl3,
+    License::Agpl,
+    License::Unlicense,
+    License::Zlib,
+    License::ZeroBsd,
+];
+
+impl License {
+    /// Get the SPDX identifier.
+    pub fn spdx(&self) -> &'static str {
+        match *self {
+            License::Mit => "MIT",
+            License::Apache => "Apache-2.0",
+            License::Bsd2Clause => "BSD-2-Clause",
+            License::Bsd3Clause => "BSD-3-Clause",
+            License::Isc => "ISC",
+            License::Mpl => "MPL-2.0",
+            License::Gpl => "GPL-3.0-or-later",
+            License::Lgpl21 => "LGPL-2.1-or-later",
+            License::Lgpl3 => "LGPL-3.0-or-later",
+            License::Agpl => "AGPL-3.0-or-later",
+            License::Unlicense => "Unlicense",
+            License::Zlib => "Zlib",
+            License::ZeroBsd => "0BSD",
+        }
+    }
+
+    /// Get the name of the file holding the license text.
+    pub fn file_name(&self) -> &'static str {
+        match *self {
+            License::Mit => "LICENSE-MIT",
+            License::Apache => "LICENSE-APACHE",
+            License::Bsd2Clause => "LICENSE-BSD-2-CLAUSE",
+            License::Bsd3Clause => "LICENSE-BSD-3-CLAUSE",
+            License::Isc => "LICENSE-ISC",
+            License::Mpl => "LICENSE-MPL-2.0",
+            License::Gpl => "LICENSE-GPL-3.0",
+            License::Lgpl21 => "LICENSE-LGPL-2.1",
+            License::Lgpl3 => "LICENSE-LGPL-3.0",
+            License::Agpl => "LICENSE-AGPL-3.0",
+            License::Unlicense => "LICENSE-UNLICENSE",
+            License::Zlib => "LICENSE-ZLIB",
+            License::ZeroBsd => "LICENSE-0BSD",
+        }
+    }
+
+    /// Get the license text template.
+    pub fn text(&self) -> &'static str {
+        match *self {
+            License::Mit => include_str!("licenses/MIT"),
+            License::Apache => include_str!("licenses/Apache-2.0"),
+            License::Bsd2Clause => include_str!("licenses/BSD-2-Clause"),
+            License::Bsd3Clause => include_str!("licenses/BSD-3-Clause"),
+            License::Isc => include_str!("licenses/ISC"),
+            License::Mpl => include_str!("licenses/MPL-2.0"),
+            License::Gpl => include_str!("licenses/GPL-3.0"),
+            License::Lgpl21 => include_str!("licenses/LGPL-2.1"),
+            License::Lgpl3 => include_str!("licenses/LGPL-3.0"),
+            License::Agpl => include_str!("licenses/AGPL-3.0"),
+            License::Unlicense => include_str!("licenses/Unlicense"),
+            License::Zlib => include_str!("licenses/Zlib"),
+            License::ZeroBsd => include_str!("licenses/0BSD"),
+        }
+    }
+
+    /// Get the files to include, as file name and text template.  The LGPL-3.0 only adds
+    /// permissions to the GPL-3.0, so both texts are included.
+    pub fn files(&self) -> Vec<(&'static str, &'static str)> {
+        let mut files = vec![(self.file_name(), self.text())];
+        if *self == License::Lgpl3 {
+            files.push((License::Gpl.file_name(), License::Gpl.text()));
+        }
+        files
+    }
+
+    /// Get the name used in the notices.
+    fn title(&self) -> &'static str {
+        match *self {
+            License::Mit => "MIT license",
+            License::Apache => "Apache License, Version 2.0",
+            License::Bsd2Clause => "BSD 2-Clause license",
+            License::Bsd3Clause => "BSD 3-Clause license",
+            License::Isc => "ISC license",
+            License::Mpl => "Mozilla Public License, Version 2.0",
+            License::Gpl => "GNU General Public License, version 3 or later",
+            License::Lgpl21 => "GNU Lesser General Public License, version 2.1 or later",
+            License::Lgpl3 => "GNU Lesser General Public License, version 3 or later",
+            License::Agpl => "GNU Affero General Public License, version 3 or later",
+            License::Unlicense => "Unlicense",
+            License::Zlib => "zlib license",
+            License::ZeroBsd => "BSD Zero Clause license",
+        }
+    }
+
+    /// Get the canonical location of the license text.
+    fn url(&self) -> &'static str {
+        match *self {
+            License::Mit => "http://opensource.org/licenses/MIT",
+            License::Apache => "http://www.apache.org/licenses/LICENSE-2.0",
+            License::Bsd2Clause => "https://opensource.org/licenses/BSD-2-Clause",
+            License::Bsd3Clause => "https://opensource.org/licenses/BSD-3-Clause",
+            License::Isc => "https://opensource.org/licenses/ISC",
+            License::Mpl => "https://mozilla.org/MPL/2.0/",
+            License::Gpl => "https://www.gnu.org/licenses/gpl-3.0.html",
+            License::Lgpl21 => "https://www.gnu.org/licenses/lgpl-2.1.html",
+            License::Lgpl3 => "https://www.gnu.org/licenses/lgpl-3.0.html",
+            License::Agpl => "https://www.gnu.org/licenses/agpl-3.0.html",
+            License::Unlicense => "https://unlicense.org/",
+            License::Zlib => "https://opensource.org/licenses/Zlib",
+            License::ZeroBsd => "https://opensource.org/licenses/0BSD",
+        }
+    }
+
+    /// Get the reference to the license used in the notices.
+    fn reference(&self) -> String {
+        format!(
+            "the {} <{} or {}>",
+            self.title(),
+            self.file_name(),
+            self.url()
+        )
+    }
+
+    /// Get the paragraphs of the notice for a file under this license alone, following the
+    /// license's own recommendation where it has one.
+    fn notice(&self) -> Vec<String> {
+        let gnu = |name: &str, version: &str| {
+            vec![
+                format!(
+                    "This program is free software: you can redistribute it and/or modify it \
+                     under the terms of the GNU {} as published by the Free Software \
+                     Foundation, either version {} of the License, or (at your option) any \
+                     later version.",
+                    name, version
+                ),
+                format!(
+                    "This program is distributed in the hope that it will be useful, but \
+                     WITHOUT ANY WARRANTY; without even the implied warranty of \
+                     MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU {} for \
+                     more details.",
+                    name
+                ),
+                format!(
+                    "You should have received a copy of the GNU {} along with this program \
+                     <{}>.  If not, see <https://www.gnu.org/licenses/>.",
+                    name,
+                    self.file_name()
+                ),
+            ]
+        };
+
+        match *self {
+            License::Mpl => vec![format!(
+                "This Source Code Form is subject to the terms of the Mozilla Public License, \
+                 v. 2.0. If a copy of the MPL was not distributed with this file, You can obtain \
+                 one at {}.",
+                self.url()
+            )],
+            License::Gpl => gnu("General Public License", "3"),
+            License::Lgpl21 => gnu("Lesser General Public License", "2.1"),
+            License::Lgpl3 => gnu("Lesser General Public License", "3"),
+            License::Agpl => gnu("Affero General Public License", "3"),
+            License::Unlicense => vec![format!(
+                "This is free and unencumbered software released into the public domain, under \
+                 {}.",
+                self.reference()
+            )],
+            _ => vec![format!("Licensed under {}. {}", self.reference(), TERMS)],
+        }
+    }
+}
+
+impl FromStr for License {
+    type Err = Error;
+
+    /// Parse an SPDX identifier, ignoring case as SPDX does, or one of the `mit` and `apache`
+    /// shorthands `--license` has always accepted.
+    fn from_str(s: &str) -> Result<License> {
+        if s.eq_ignore_ascii_case("apache") {
+            return Ok(License::Apache);
+        }
+        LICENSES
+            .iter()
+            .find(|license| license.spdx().eq_ignore_ascii_case(s))
+            .cloned()
+            .ok_or_else(|| ErrorKind::InvalidLicense(s.to_string()).into())
+    }
+}
+
+impl fmt::Display for License {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.spdx())
+    }
+}
+
+/// Parse a `--license` value: `both` (MIT or Apache-2.0), `none`, or an SPDX identifier or
+/// several joined by `OR`, of which the user may choose.
+pub fn parse_licenses(s: &str) -> Result<Vec<License>> {
+    match s {
+        "both" => return Ok(vec![License::Mit, License::Apache]),
+        "none" => return Ok(Vec::new()),
+        _ => {}
+    }
+
+    let mut licenses = Vec::new();
+    let mut expected_license = true;
+    for token in s.split_whitespace() {
+        if expected_license {
+            let license = token.parse::<License>()?;
+            if !licenses.contains(&license) {
+                licenses.push(license);
+            }
+        } else if !token.eq_ignore_ascii_case("OR") {
+            return Err(ErrorKind::InvalidLicense(s.to_string()).into());
+        }
+        expected_license = !expected_license;
+    }
+
+    // An empty expression, or one ending in `OR`.
+    if expected_license {
+        return Err(ErrorKind::InvalidLicense(s.to_string()).into());
+    }
+    Ok(licenses)
+}
+
+/// Get the `license` expression for `Cargo.toml`, if there are any licenses.
+pub fn expression(licenses: &[License]) -> Option<String> {
+    if licenses.is_empty() {
+        return None;
+    }
+
+    let spdx = licenses.iter().map(License::spdx).collect::<Vec<_>>();
+    Some(spdx.join(" OR "))
+}
+
+/// Get the notice the generated source files start with, as comment lines following the copyright
+/// line, if there are any licenses.  A file under a choice of licenses refers to each of them.
+pub fn notice(licenses: &[License]) -> Option<String> {
+    let paragraphs = match licenses {
+        [] => return None,
+        [license] => license.notice(),
+        [init @ .., last] => {
+            let references = init.iter().map(License::reference).collect::<Vec<_>>();
+            vec![format!(
+                "Licensed under {} or {}, at your option. {}",
+                references.join(", "),
+                last.reference(),
+                TERMS
+            )]
+        }
+    };
+
+    let paragraphs = paragraphs
+        .iter()
+        .map(|paragraph| wrap(paragraph, NOTICE_WIDTH))
+        .collect::<Vec<_>>();
+    Some(paragraphs.join("//\n"))
+}
+
+/// Wrap the given text into `// ` comment lines no wider than the given width, where a word
+/// allows.
+fn wrap(text: &str, width: usize) -> String {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+
+    for word in text.split_whitespace() {
+        if !line.is_empty() && line.len() + 1 + word.len() > width {
+            lines.push(line);
+            line = String::new();
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+    }
+    lines.push(line);
+
+    lines
+        .iter()
+        .map(|line| format!("// {}\n", line.trim_end()))
+        .collect()
+}
+
+/// Get the current year, for the copyright lines.
+pub fn current_year() -> u64 {
+    let days = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|since_epoch| since_epoch.as_secs() / SECS_PER_DAY)
+        .unwrap_or(0);
+    year(days)
+}
+
+/// Get the year of the given day, counted from the Unix epoch, in the proleptic Gregorian
+/// calendar.
+fn year(days: u64) -> u64 {
+    // Count from 0000-03-01, so leap days end each 4, 100 and 400 year cycle.
+    let days = days + DAYS_TO_EPOCH;
+    let era = days / DAYS_PER_ERA;
+    let day_of_era = days % DAYS_PER_ERA;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    // January and February belong to the next year.
+    let january_or_february = day_of_year >= 306;
+
+    era * 400 + year_of_era + u64::from(january_or_february)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{expression, parse_licenses, wrap, year, License};
+
+    #[test]
+    fn shorthands_are_parsed() {
+        assert_eq!(
+            parse_licenses("both").expect("Unable to parse both!"),
+            vec![License::Mit, License::Apache]
+        );
+        assert!(parse_licenses("none")
+            .expect("Unable to parse none!")
+            .is_empty());
+        assert_eq!(
+            parse_licenses("apache").expect("Unable to parse apache!"),
+            vec![License::Apache]
+        );
+    }
+
+    #[test]
+    fn expressions_are_parsed() {
+        assert_eq!(
+            parse_licenses("GPL-3.0-or-later").expect("Unable to parse GPL!"),
+            vec![License::Gpl]
+        );
+        assert_eq!(
+            parse_licenses("mpl-2.0 or 0BSD  OR  Zlib").expect("Unable to parse the expression!"),
+            vec![License::Mpl, License::ZeroBsd, License::Zlib]
+        );
+        assert_eq!(
+            parse_licenses("MIT OR Apache-2.0 OR MIT").expect("Unable to parse duplicates!"),
+            vec![License::Mit, License::Apache]
+        );
+    }
+
+    #[test]
+    fn invalid_expressions_are_rejected() {
+        for invalid in &[
+            "",
+            "GPL-3.0",
+            "MIT AND Apache-2.0",
+            "MIT OR",
+            "OR MIT",
+            "MIT Apache-2.0",
+        ] {
+            assert!(
+                parse_licenses(invalid).is_err(),
+                "'{}' was accepted",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn expressions_join_with_or() {
+        assert_eq!(expression(&[]), None);
+        assert_eq!(expression(&[License::Isc]), Some("ISC".to_string()));
+        assert_eq!(
+            expression(&[License::Lgpl3, License::Bsd2Clause]),
+            Some("LGPL-3.0-or-later OR BSD-2-Clause".to_string())
+        );
+    }
+
+    #[test]
+    fn text_is_wrapped_into_comment_lines() {
+        assert_eq!(
+            wrap("one two  three   four", 9),
+            "// one two\n// three\n// four\n"
+        );
+        assert_eq!(
+            wrap("<https://example.com/long>", 9),
+            "// <https://example.com/long>\n"
+        );
+        for line in wrap(&"word ".repeat(40), 77).lines() {
+            assert!(line.len() <= 80, "'{}' is too wide", line);
+        }
+    }
+
+    #[test]
+    fn years_follow_the_gregorian_calendar() {
+        assert_eq!(year(0), 1970);
+        assert_eq!(year(10_956), 1999);
+        assert_eq!(year(10_957), 2000);
+        assert_eq!(year(19_782), 2024);
+        assert_eq!(year(20_743), 2026);
+    }
+}
@@ -0,0 +1,722 @@
+// Copyright (c) 2017 cargo-cli developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! `cargo-cli` Cargo.toml editing.
+//!
+//! Edits are applied in place on the parsed document, so any tables, keys, comments or formatting
+//! that `cargo new` (or a user) wrote into the manifest survive untouched.
+
+use error::{ErrorKind, Result};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+use toml_edit::{value, Array, DocumentMut, InlineTable, Item, Table, TableLike};
+use util::read_to_string;
+
+/// A dependency to add to the manifest.
+///
+/// Dependencies deserialize from the same forms `Cargo.toml` uses, i.e. `"1.0"` or
+/// `{ version = "1.0", features = [...], ... }`.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(from = "Spec")]
+pub struct Dependency {
+    /// The version requirement.
+    version: Option<String>,
+    /// The features to enable.
+    features: Vec<String>,
+    /// Should the default features be enabled?
+    default_features: Option<bool>,
+    /// Is the dependency optional?
+    optional: bool,
+    /// The local path of the dependency.
+    path: Option<String>,
+    /// The git repository of the dependency.
+    git: Option<String>,
+    /// The git branch to use.
+    branch: Option<String>,
+    /// The git tag to use.
+    tag: Option<String>,
+    /// The git revision to use.
+    rev: Option<String>,
+    /// The name of the package, when the dependency is renamed.
+    package: Option<String>,
+    /// The alternate registry the dependency comes from.
+    registry: Option<String>,
+    /// Is the dependency inherited from `[workspace.dependencies]`?
+    workspace: bool,
+}
+
+/// The forms a dependency takes in `Cargo.toml`.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Spec {
+    /// `name = "1.0"`
+    Version(String),
+    /// `name = { version = "1.0", ... }`
+    Detailed(DetailedSpec),
+}
+
+/// The table form of a dependency.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+struct DetailedSpec {
+    /// The version requirement.
+    version: Option<String>,
+    /// The features to enable.
+    #[serde(default)]
+    features: Vec<String>,
+    /// Should the default features be enabled?
+    #[serde(alias = "default_features")]
+    default_features: Option<bool>,
+    /// Is the dependency optional?
+    #[serde(default)]
+    optional: bool,
+    /// The local path of the dependency.
+    path: Option<String>,
+    /// The git repository of the dependency.
+    git: Option<String>,
+    /// The git branch to use.
+    branch: Option<String>,
+    /// The git tag to use.
+    tag: Option<String>,
+    /// The git revision to use.
+    rev: Option<String>,
+    /// The name of the package, when the dependency is renamed.
+    package: Option<String>,
+    /// The alternate registry the dependency comes from.
+    registry: Option<String>,
+    /// Is the dependency inherited from `[workspace.dependencies]`?
+    #[serde(default)]
+    workspace: bool,
+}
+
+impl From<Spec> for Dependency {
+    fn from(spec: Spec) -> Dependency {
+        match spec {
+            Spec::Version(version) => Dependency::new(&version),
+            Spec::Detailed(spec) => Dependency {
+                version: spec.version,
+                features: spec.features,
+                default_features: spec.default_features,
+                optional: spec.optional,
+                path: spec.path,
+                git: spec.git,
+                branch: spec.branch,
+                tag: spec.tag,
+                rev: spec.rev,
+                package: spec.package,
+                registry: spec.registry,
+                workspace: spec.workspace,
+            },
+        }
+    }
+}
+
+impl Dependency {
+    /// Create a new dependency on the given version.
+    pub fn new(version: &str) -> Dependency {
+        Dependency {
+            version: Some(version.to_string()),
+            ..Default::default()
+        }
+    }
+
+    /// Enable the given features.
+    pub fn features(mut self, features: &[&str]) -> Dependency {
+        self.features
+            .extend(features.iter().map(|feature| feature.to_string()));
+        self
+    }
+
+    /// Take the dependency from the given alternate registry, unless it names one already.
+    pub fn registry(mut self, registry: &str) -> Dependency {
+        if self.registry.is_none() {
+            self.registry = Some(registry.to_string());
+        }
+        self
+    }
+
+    /// Take the dependency from the given local path.
+    pub fn path(mut self, path: &str) -> Dependency {
+        self.path = Some(path.to_string());
+        self
+    }
+
+    /// Get the `version` value.
+    pub fn version(&self) -> Option<&str> {
+        self.version.as_deref()
+    }
+
+    /// Set the version requirement.
+    pub fn set_version(&mut self, version: &str) {
+        self.version = Some(version.to_string());
+    }
+
+    /// Get the name of the package the dependency refers to, which differs from the dependency
+    /// name when it is renamed.
+    pub fn package_name<'a>(&'a self, name: &'a str) -> &'a str {
+        self.package.as_deref().unwrap_or(name)
+    }
+
+    /// Does the dependency come from a registry, rather than a path, a git repository or the
+    /// workspace?
+    pub fn is_registry(&self) -> bool {
+        self.path.is_none() && self.git.is_none() && !self.workspace
+    }
+
+    /// Split the dependency into its `[workspace.dependencies]` entry and the member entry
+    /// inheriting it, which keeps only the features and whether it is optional.
+    pub fn inherit(&self) -> (Dependency, Dependency) {
+        let shared = Dependency {
+            features: Vec::new(),
+            optional: false,
+            ..self.clone()
+        };
+        let member = Dependency {
+            features: self.features.clone(),
+            optional: self.optional,
+            workspace: true,
+            ..Default::default()
+        };
+        (shared, member)
+    }
+
+    /// Convert to a manifest entry, i.e. `"1.0"` or `{ version = "1.0", features = [...] }`.
+    fn to_item(&self) -> Item {
+        if let (Some(version), true) = (self.version.as_ref(), self.is_version_only()) {
+            return value(version.as_str());
+        }
+
+        let mut table = InlineTable::new();
+        if self.workspace {
+            table.insert("workspace", true.into());
+        }
+        let strings = [
+            ("package", &self.package),
+            ("version", &self.version),
+            ("path", &self.path),
+            ("git", &self.git),
+            ("branch", &self.branch),
+            ("tag", &self.tag),
+            ("rev", &self.rev),
+            ("registry", &self.registry),
+        ];
+        for &(key, val) in &strings {
+            if let Some(val) = val {
+                table.insert(key, val.as_str().into());
+            }
+        }
+        if !self.features.is_empty() {
+            table.insert("features", self.features.iter().collect::<Array>().into());
+        }
+        if let Some(default_features) = self.default_features {
+            table.insert("default-features", default_features.into());
+        }
+        if self.optional {
+            table.insert("optional", true.into());
+        }
+        value(table)
+    }
+
+    /// Is the version the only thing set?
+    fn is_version_only(&self) -> bool {
+        self.features.is_empty()
+            && self.default_features.is_none()
+            && !self.optional
+            && self.path.is_none()
+            && self.git.is_none()
+            && self.branch.is_none()
+            && self.tag.is_none()
+            && self.rev.is_none()
+            && self.package.is_none()
+            && self.registry.is_none()
+            && !self.workspace
+    }
+}
+
+impl fmt::Display for Dependency {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_item())
+    }
+}
+
+/// A lint setting to add to the manifest, i.e. `name = { level = "warn", check-cfg = [...] }`.
+#[derive(Clone, Debug)]
+pub struct Lint {
+    /// The lint level, e.g. `warn`.
+    level: String,
+    /// The cfgs to declare as expected, for the `unexpected_cfgs` lint.
+    check_cfg: Vec<String>,
+}
+
+impl Lint {
+    /// Create a new lint setting of the given level.
+    pub fn new(level: &str) -> Lint {
+        Lint {
+            level: level.to_string(),
+            check_cfg: Vec::new(),
+        }
+    }
+
+    /// Declare the given cfgs, e.g. `cfg(has_foo)`, as expected.
+    pub fn check_cfg(mut self, cfgs: &[&str]) -> Lint {
+        self.check_cfg
+            .extend(cfgs.iter().map(|cfg| cfg.to_string()));
+        self
+    }
+
+    /// Convert to a manifest entry, i.e. `"warn"` or `{ level = "warn", check-cfg = [...] }`.
+    fn to_item(&self) -> Item {
+        if self.check_cfg.is_empty() {
+            return value(self.level.as_str());
+        }
+
+        let mut table = InlineTable::new();
+        table.insert("level", self.level.as_str().into());
+        table.insert("check-cfg", self.check_cfg.iter().collect::<Array>().into());
+        value(table)
+    }
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_item())
+    }
+}
+
+/// The kind of a dependency table.
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum DepKind {
+    /// `[dependencies]`
+    Normal,
+    /// `[dev-dependencies]`
+    Dev,
+    /// `[build-dependencies]`
+    Build,
+}
+
+impl DepKind {
+    /// Get the manifest key of the table.
+    fn key(self) -> &'static str {
+        match self {
+            DepKind::Normal => "dependencies",
+            DepKind::Dev => "dev-dependencies",
+            DepKind::Build => "build-dependencies",
+        }
+    }
+}
+
+/// A dependency table in the manifest, e.g. `[dev-dependencies]` or
+/// `[target.'cfg(unix)'.dependencies]`.
+#[derive(Clone, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub struct DepTable {
+    /// The platform the dependencies are specific to.
+    target: Option<String>,
+    /// The kind of dependencies.
+    kind: DepKind,
+}
+
+impl DepTable {
+    /// Create a new dependency table of the given kind, for every platform.
+    pub fn new(kind: DepKind) -> DepTable {
+        DepTable { target: None, kind }
+    }
+
+    /// Make the table specific to the given platform, i.e. a target triple or `cfg(...)`.
+    pub fn target(mut self, target: &str) -> DepTable {
+        self.target = Some(target.to_string());
+        self
+    }
+
+    /// Is this the plain `[dependencies]` table?
+    pub fn is_normal(&self) -> bool {
+        self.target.is_none() && self.kind == DepKind::Normal
+    }
+}
+
+impl fmt::Display for DepTable {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.target {
+            Some(ref target) => write!(f, "target.'{}'.{}", target, self.kind.key()),
+            None => write!(f, "{}", self.kind.key()),
+        }
+    }
+}
+
+/// Changes to a package manifest, which come with a generated file.
+#[derive(Clone, Debug, Default)]
+pub struct ManifestPatch {
+    /// The `[package]` values to set.
+    package_values: Vec<(String, String)>,
+    /// The dependencies to add, by dependency table.
+    dependencies: BTreeMap<DepTable, BTreeMap<String, Dependency>>,
+    /// The lints to set, by tool, e.g. `rust` for `[lints.rust]`.
+    lints: BTreeMap<String, BTreeMap<String, Lint>>,
+}
+
+impl ManifestPatch {
+    /// Create an empty manifest patch.
+    pub fn new() -> ManifestPatch {
+        ManifestPatch::default()
+    }
+
+    /// Set a `[package]` value.
+    pub fn package_value(mut self, key: &str, val: &str) -> ManifestPatch {
+        self.package_values.push((key.to_string(), val.to_string()));
+        self
+    }
+
+    /// Add a dependency to the given dependency table.
+    pub fn dependency(mut self, table: DepTable, name: &str, dep: Dependency) -> ManifestPatch {
+        self.dependencies
+            .entry(table)
+            .or_default()
+            .insert(name.to_string(), dep);
+        self
+    }
+
+    /// Set a lint of the given tool, e.g. `rust` for `[lints.rust]`.
+    pub fn lint(mut self, tool: &str, name: &str, lint: Lint) -> ManifestPatch {
+        self.lints
+            .entry(tool.to_string())
+            .or_default()
+            .insert(name.to_string(), lint);
+        self
+    }
+
+    /// Get the `package_values` value.
+    pub fn package_values(&self) -> &[(String, String)] {
+        &self.package_values
+    }
+
+    /// Get the `dependencies` value.
+    pub fn dependencies(&self) -> &BTreeMap<DepTable, BTreeMap<String, Dependency>> {
+        &self.dependencies
+    }
+
+    /// Get the `lints` value.
+    pub fn lints(&self) -> &BTreeMap<String, BTreeMap<String, Lint>> {
+        &self.lints
+    }
+}
+
+/// A format-preserving `Cargo.toml` manifest.
+pub struct Manifest {
+    /// The parsed manifest document.
+    doc: DocumentMut,
+}
+
+impl Manifest {
+    /// Read and parse the `Cargo.toml` at the given path.
+    pub fn open(path: &Path) -> Result<Manifest> {
+        Ok(Manifest {
+            doc: read_to_string(path)?.parse::<DocumentMut>()?,
+        })
+    }
+
+    /// Create the manifest of a virtual workspace with the given members and resolver.
+    pub fn workspace(members: &[&str], resolver: &str) -> Manifest {
+        let mut workspace = Table::new();
+        workspace.insert("members", value(members.iter().copied().collect::<Array>()));
+        workspace.insert("resolver", value(resolver));
+
+        let mut doc = DocumentMut::new();
+        doc.insert("workspace", Item::Table(workspace));
+        Manifest { doc }
+    }
+
+    /// Get a string value from the `[package]` table.
+    pub fn package_value(&self, key: &str) -> Option<&str> {
+        self.doc
+            .get("package")
+            .and_then(|package| package.get(key))
+            .and_then(|item| item.as_str())
+    }
+
+    /// Set a string value in the `[package]` table, replacing any existing value for the key.
+    pub fn set_package_value(&mut self, key: &str, val: &str) -> Result<()> {
+        self.package_mut()?.insert(key, value(val));
+        Ok(())
+    }
+
+    /// Inherit the given `[package]` key from the workspace, i.e. `edition.workspace = true`.
+    pub fn inherit_package_value(&mut self, key: &str) -> Result<()> {
+        let mut inherited = Table::new();
+        inherited.set_dotted(true);
+        inherited.insert("workspace", value(true));
+        self.package_mut()?.insert(key, Item::Table(inherited));
+        Ok(())
+    }
+
+    /// Is this the manifest of a workspace?
+    pub fn is_workspace(&self) -> bool {
+        self.doc.contains_key("workspace")
+    }
+
+    /// Get a string value from the `[workspace.package]` table.
+    pub fn workspace_package_value(&self, key: &str) -> Option<&str> {
+        self.workspace_item(&["package", key])
+            .and_then(|item| item.as_str())
+    }
+
+    /// Set a string value in the `[workspace.package]` table, for members to inherit.
+    pub fn set_workspace_package_value(&mut self, key: &str, val: &str) -> Result<()> {
+        let package = self
+            .table_mut("workspace")
+            .as_table_like_mut()
+            .ok_or_else(|| ErrorKind::InvalidManifestTable("workspace".to_string()))?
+            .entry("package")
+            .or_insert(Item::Table(Table::new()))
+            .as_table_like_mut()
+            .ok_or_else(|| ErrorKind::InvalidManifestTable("workspace.package".to_string()))?;
+        package.insert(key, value(val));
+        Ok(())
+    }
+
+    /// Does the `[workspace.package]` table define the given key for members to inherit?
+    pub fn workspace_defines(&self, key: &str) -> bool {
+        self.workspace_item(&["package", key]).is_some()
+    }
+
+    /// Get the paths (or globs) listed under the given key of the `[workspace]` table, i.e.
+    /// `members` or `exclude`.
+    pub fn workspace_paths(&self, key: &str) -> Vec<String> {
+        self.workspace_item(&[key])
+            .and_then(|item| item.as_array())
+            .map(|paths| {
+                paths
+                    .iter()
+                    .filter_map(|path| path.as_str())
+                    .map(|path| path.to_string())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Add a path to the `members` of the `[workspace]` table.
+    pub fn add_workspace_member(&mut self, member: &str) -> Result<()> {
+        let members = self
+            .table_mut("workspace")
+            .as_table_like_mut()
+            .ok_or_else(|| ErrorKind::InvalidManifestTable("workspace".to_string()))?
+            .entry("members")
+            .or_insert(value(Array::new()))
+            .as_array_mut()
+            .ok_or_else(|| ErrorKind::InvalidManifestTable("workspace.members".to_string()))?;
+        members.push(member);
+        Ok(())
+    }
+
+    /// Does the manifest have a `[workspace.dependencies]` table?
+    pub fn has_workspace_dependencies(&self) -> bool {
+        self.workspace_item(&["dependencies"]).is_some()
+    }
+
+    /// Add a dependency to the `[workspace.dependencies]` table, unless it is already present.
+    pub fn add_workspace_dependency(&mut self, name: &str, dep: &Dependency) -> Result<()> {
+        let dependencies = self
+            .table_mut("workspace")
+            .as_table_like_mut()
+            .ok_or_else(|| ErrorKind::InvalidManifestTable("workspace".to_string()))?
+            .entry("dependencies")
+            .or_insert(Item::Table(Table::new()))
+            .as_table_like_mut()
+            .ok_or_else(|| ErrorKind::InvalidManifestTable("workspace.dependencies".to_string()))?;
+        if dependencies.get(name).is_none() {
+            dependencies.insert(name, dep.to_item());
+        }
+        Ok(())
+    }
+
+    /// Add the given dependencies to the given dependency table.  Dependencies that are already
+    /// present in the manifest are left as they are.
+    pub fn add_dependencies(
+        &mut self,
+        table: &DepTable,
+        deps: &BTreeMap<String, Dependency>,
+    ) -> Result<()> {
+        let dependencies = match table.target {
+            Some(ref target) => {
+                // Only the innermost table gets a header, i.e. `[target.'cfg(unix)'.dependencies]`.
+                let platform = child_table_mut(self.table_mut("target"), "target", target)?;
+                child_table_mut(platform, &table.to_string(), table.kind.key())?
+            }
+            None => self.table_mut(table.kind.key()),
+        };
+
+        let dependencies = dependencies
+            .as_table_like_mut()
+            .ok_or_else(|| ErrorKind::InvalidManifestTable(table.to_string()))?;
+        for (name, dep) in deps {
+            if dependencies.get(name).is_none() {
+                dependencies.insert(name, dep.to_item());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Does the manifest inherit its lints from the workspace, i.e. `lints.workspace = true`?
+    pub fn inherits_lints(&self) -> bool {
+        self.doc
+            .get("lints")
+            .and_then(|lints| lints.get("workspace"))
+            .and_then(|inherited| inherited.as_bool())
+            .unwrap_or(false)
+    }
+
+    /// Add the given lints of the given tool to the `[lints.<tool>]` table.  Lints that are
+    /// already set are left as they are.
+    pub fn add_lints(&mut self, tool: &str, lints: &BTreeMap<String, Lint>) -> Result<()> {
+        let path = format!("lints.{}", tool);
+        let table = child_table_mut(self.table_mut("lints"), "lints", tool)?;
+        insert_lints(table, &path, lints)
+    }
+
+    /// Add the given lints of the given tool to the `[workspace.lints.<tool>]` table, for members
+    /// to inherit.  Lints that are already set are left as they are.
+    pub fn add_workspace_lints(
+        &mut self,
+        tool: &str,
+        lints: &BTreeMap<String, Lint>,
+    ) -> Result<()> {
+        let path = format!("workspace.lints.{}", tool);
+        let workspace_lints = child_table_mut(self.table_mut("workspace"), "workspace", "lints")?;
+        let table = child_table_mut(workspace_lints, "workspace.lints", tool)?;
+        insert_lints(table, &path, lints)
+    }
+
+    /// Get the item at the given path of keys within the `[workspace]` table.
+    fn workspace_item(&self, keys: &[&str]) -> Option<&Item> {
+        keys.iter()
+            .try_fold(self.doc.get("workspace")?, |item, key| item.get(key))
+    }
+
+    /// Get the `[package]` table, creating it if necessary.  Errors if `package` isn't a table.
+    fn package_mut(&mut self) -> Result<&mut dyn TableLike> {
+        self.table_mut("package")
+            .as_table_like_mut()
+            .ok_or_else(|| ErrorKind::InvalidManifestTable("package".to_string()).into())
+    }
+
+    /// Get the top-level table with the given name, creating it if necessary.
+    fn table_mut(&mut self, name: &str) -> &mut Item {
+        self.doc
+            .as_table_mut()
+            .entry(name)
+            .or_insert_with(|| Item::Table(Table::new()))
+    }
+}
+
+impl fmt::Display for Manifest {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.doc)
+    }
+}
+
+/// Get the table with the given key within the table `item` (at `path`), creating it as an
+/// implicit table, i.e. one without a header of its own, if necessary.
+fn child_table_mut<'a>(item: &'a mut Item, path: &str, key: &str) -> Result<&'a mut Item> {
+    let table = item
+        .as_table_mut()
+        .ok_or_else(|| ErrorKind::InvalidManifestTable(path.to_string()))?;
+    table.set_implicit(true);
+
+    Ok(table.entry(key).or_insert_with(|| {
+        let mut child = Table::new();
+        child.set_implicit(true);
+        Item::Table(child)
+    }))
+}
+
+/// Insert the given lints into the lint table `item` (at `path`), keeping those already set.
+fn insert_lints(item: &mut Item, path: &str, lints: &BTreeMap<String, Lint>) -> Result<()> {
+    let table = item
+        .as_table_like_mut()
+        .ok_or_else(|| ErrorKind::InvalidManifestTable(path.to_string()))?;
+    for (name, lint) in lints {
+        if table.get(name).is_none() {
+            table.insert(name, lint.to_item());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Lint, Manifest};
+    use error::ErrorKind;
+    use std::collections::BTreeMap;
+
+    fn manifest(toml: &str) -> Manifest {
+        Manifest {
+            doc: toml.parse().expect("Unable to parse the manifest!"),
+        }
+    }
+
+    #[test]
+    fn package_values_are_set_in_inline_tables() {
+        let mut manifest = manifest("package = { name = \"demo\" }\n");
+        manifest
+            .set_package_value("edition", "2021")
+            .expect("Unable to set the edition!");
+        manifest
+            .inherit_package_value("license")
+            .expect("Unable to inherit the license!");
+
+        assert_eq!(manifest.package_value("name"), Some("demo"));
+        assert_eq!(manifest.package_value("edition"), Some("2021"));
+        assert!(manifest
+            .to_string()
+            .contains("license = { workspace = true }"));
+    }
+
+    #[test]
+    fn non_table_packages_are_rejected() {
+        let mut manifest = manifest("package = \"demo\"\n");
+
+        for result in &[
+            manifest.set_package_value("edition", "2021"),
+            manifest.inherit_package_value("license"),
+        ] {
+            match *result
+                .as_ref()
+                .expect_err("A string package was accepted!")
+                .kind()
+            {
+                ErrorKind::InvalidManifestTable(ref table) => assert_eq!(table, "package"),
+                ref kind => panic!("unexpected error: {}", kind),
+            }
+        }
+    }
+
+    #[test]
+    fn lints_are_added_once() {
+        let mut lints = BTreeMap::new();
+        lints.insert(
+            "unexpected_cfgs".to_string(),
+            Lint::new("warn").check_cfg(&["cfg(has_foo)"]),
+        );
+        let mut manifest = manifest("[package]\nname = \"demo\"\n");
+        manifest
+            .add_lints("rust", &lints)
+            .expect("Unable to add the lints!");
+        lints.insert("unexpected_cfgs".to_string(), Lint::new("allow"));
+        manifest
+            .add_lints("rust", &lints)
+            .expect("Unable to add the lints again!");
+
+        assert_eq!(
+            manifest.to_string(),
+            "[package]\nname = \"demo\"\n\n[lints.rust]\n\
+             unexpected_cfgs = { level = \"warn\", check-cfg = [\"cfg(has_foo)\"] }\n"
+        );
+        assert!(!manifest.inherits_lints());
+        assert!(self::manifest("lints.workspace = true\n").inherits_lints());
+    }
+}
@@ -0,0 +1,381 @@
+// Copyright (c) 2017 cargo-cli developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! `cargo-cli` file generators.
+//!
+//! Each generated file comes from a [`FileGenerator`], which renders its contents from the
+//! templates and may patch the manifest of the package it belongs to.  The built-in outputs are
+//! generators like any other, and more can be registered with
+//! [`Generator::register`](::generator::Generator::register).
+
+use error::Result;
+use manifest::{Lint, ManifestPatch};
+use tmpl::{Layout, Templates};
+
+/// The package a generated file belongs to.  With the default layout these are all the same
+/// package, while the split layout puts them at the workspace root, in the binary package and in
+/// the `-core` library package respectively.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Destination {
+    /// The project root.
+    Root,
+    /// The binary package.
+    Binary,
+    /// The library package.
+    Library,
+}
+
+/// A producer of a generated file.
+pub trait FileGenerator {
+    /// Get the path of the file, relative to its package.
+    fn path(&self) -> &str;
+
+    /// Get the package the file belongs to.
+    fn destination(&self) -> Destination {
+        Destination::Binary
+    }
+
+    /// May the file replace an existing one, such as the `src/main.rs` created by `cargo new`?
+    fn replaces(&self) -> bool {
+        false
+    }
+
+    /// Render the file contents, or `None` when the file isn't generated.
+    fn contents(&self, templates: &Templates) -> Result<Option<String>>;
+
+    /// Get the changes to the package manifest that come with the file.  The `[package]` values
+    /// the workspace defines are inherited instead, and with the split layout the ones of the
+    /// project root are shared by both packages.
+    fn manifest_patch(&self, _templates: &Templates) -> ManifestPatch {
+        ManifestPatch::new()
+    }
+}
+
+/// Prefix the given Rust source with the license header, when there is a license.
+fn with_header(templates: &Templates, source: String) -> Result<String> {
+    if templates.has_license() {
+        Ok(templates.prefix()? + &source)
+    } else {
+        Ok(source)
+    }
+}
+
+/// The `src/main.rs` generator.
+pub struct MainFile;
+
+impl FileGenerator for MainFile {
+    fn path(&self) -> &str {
+        "src/main.rs"
+    }
+
+    fn replaces(&self) -> bool {
+        true
+    }
+
+    fn contents(&self, templates: &Templates) -> Result<Option<String>> {
+        with_header(templates, templates.main()?).map(Some)
+    }
+}
+
+/// The `src/run.rs` generator.
+pub struct RunFile;
+
+impl FileGenerator for RunFile {
+    fn path(&self) -> &str {
+        "src/run.rs"
+    }
+
+    fn contents(&self, templates: &Templates) -> Result<Option<String>> {
+        with_header(templates, templates.run()?).map(Some)
+    }
+}
+
+/// The `src/error.rs` generator, which also declares the cfgs the error handling crate tests.
+pub struct ErrorFile;
+
+impl FileGenerator for ErrorFile {
+    fn path(&self) -> &str {
+        "src/error.rs"
+    }
+
+    fn destination(&self) -> Destination {
+        Destination::Library
+    }
+
+    fn contents(&self, templates: &Templates) -> Result<Option<String>> {
+        with_header(templates, templates.error()?).map(Some)
+    }
+
+    fn manifest_patch(&self, templates: &Templates) -> ManifestPatch {
+        let cfgs = templates.check_cfgs();
+        if cfgs.is_empty() {
+            ManifestPatch::new()
+        } else {
+            let lint = Lint::new("warn").check_cfg(cfgs);
+            ManifestPatch::new().lint("rust", "unexpected_cfgs", lint)
+        }
+    }
+}
+
+/// The `src/lib.rs` generator of the split layout.
+pub struct LibFile;
+
+impl FileGenerator for LibFile {
+    fn path(&self) -> &str {
+        "src/lib.rs"
+    }
+
+    fn destination(&self) -> Destination {
+        Destination::Library
+    }
+
+    fn replaces(&self) -> bool {
+        true
+    }
+
+    fn contents(&self, templates: &Templates) -> Result<Option<String>> {
+        with_header(templates, templates.lib()?).map(Some)
+    }
+}
+
+/// The generator of a license file, such as `LICENSE-MIT`, which also sets the `license`
+/// expression.
+pub struct LicenseFile {
+    /// The license file name.
+    file_name: &'static str,
+}
+
+impl LicenseFile {
+    /// Create the generator of the license file with the given name.
+    pub fn new(file_name: &'static str) -> LicenseFile {
+        LicenseFile { file_name }
+    }
+}
+
+impl FileGenerator for LicenseFile {
+    fn path(&self) -> &str {
+        self.file_name
+    }
+
+    fn destination(&self) -> Destination {
+        Destination::Root
+    }
+
+    fn contents(&self, templates: &Templates) -> Result<Option<String>> {
+        templates.license_file(self.file_name).transpose()
+    }
+
+    fn manifest_patch(&self, templates: &Templates) -> ManifestPatch {
+        match templates.cargo_toml_license() {
+            Some(license) => ManifestPatch::new().package_value("license", &license),
+            None => ManifestPatch::new(),
+        }
+    }
+}
+
+/// The `README.md` generator.
+pub struct ReadmeFile;
+
+impl FileGenerator for ReadmeFile {
+    fn path(&self) -> &str {
+        "README.md"
+    }
+
+    fn destination(&self) -> Destination {
+        Destination::Root
+    }
+
+    fn contents(&self, templates: &Templates) -> Result<Option<String>> {
+        templates.readme().transpose()
+    }
+
+    fn manifest_patch(&self, templates: &Templates) -> ManifestPatch {
+        ManifestPatch::new().package_value("readme", templates.cargo_toml_readme())
+    }
+}
+
+/// Get the generators of the license files.
+pub fn licenses(templates: &Templates) -> Vec<Box<dyn FileGenerator>> {
+    templates
+        .license_file_names()
+        .into_iter()
+        .map(|file_name| Box::new(LicenseFile::new(file_name)) as Box<dyn FileGenerator>)
+        .collect()
+}
+
+/// Get the built-in generators of the given layout, in the order their files are staged.
+pub fn builtin(templates: &Templates, layout: Layout) -> Vec<Box<dyn FileGenerator>> {
+    let mut generators: Vec<Box<dyn FileGenerator>> = vec![Box::new(MainFile)];
+    if layout == Layout::Split {
+        generators.push(Box::new(LibFile));
+    }
+    generators.push(Box::new(ErrorFile));
+    generators.push(Box::new(RunFile));
+    generators.extend(licenses(templates));
+    generators.push(Box::new(ReadmeFile));
+    generators
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        builtin, Destination, ErrorFile, FileGenerator, LibFile, LicenseFile, MainFile, ReadmeFile,
+        RunFile,
+    };
+    use license::{self, License};
+    use tmpl::{ArgParser, ErrorCrate, Layout, Templates};
+
+    /// Create the templates of a licensed `demo` project with a README.md.
+    fn templates() -> Templates {
+        Templates::new(
+            "demo",
+            ArgParser::Clap,
+            ErrorCrate::ErrorChain,
+            &[License::Mit, License::Apache],
+            true,
+            false,
+        )
+    }
+
+    /// Render the file of the given generator.
+    fn render(generator: &dyn FileGenerator, templates: &Templates) -> String {
+        generator
+            .contents(templates)
+            .expect("Unable to render the file!")
+            .expect("The file isn't generated!")
+    }
+
+    #[test]
+    fn sources_start_with_the_license_header() {
+        let templates = templates();
+        let header = format!(
+            "// Copyright (c) {} demo developers\n",
+            license::current_year()
+        );
+        let sources: [(&dyn FileGenerator, &str, Destination, &str); 4] = [
+            (&MainFile, "src/main.rs", Destination::Binary, "fn main() {"),
+            (
+                &RunFile,
+                "src/run.rs",
+                Destination::Binary,
+                "pub fn run() -> Result<i32> {",
+            ),
+            (
+                &ErrorFile,
+                "src/error.rs",
+                Destination::Library,
+                "error_chain!{",
+            ),
+            (
+                &LibFile,
+                "src/lib.rs",
+                Destination::Library,
+                "pub fn greet(name: &str)",
+            ),
+        ];
+
+        for &(generator, path, destination, line) in &sources {
+            assert_eq!(generator.path(), path);
+            assert_eq!(generator.destination(), destination);
+            let contents = render(generator, &templates);
+            assert!(contents.starts_with(&header), "{}", contents);
+            assert!(contents.contains(line), "{}", contents);
+        }
+        assert!(MainFile.replaces() && LibFile.replaces());
+        assert!(!RunFile.replaces() && !ErrorFile.replaces());
+    }
+
+    #[test]
+    fn sources_have_no_header_without_a_license() {
+        let templates = Templates::new("demo", ArgParser::Argh, ErrorCrate::Std, &[], false, false);
+
+        assert!(render(&MainFile, &templates).starts_with("//! `demo` 0.1.0\n"));
+        assert!(render(&RunFile, &templates).starts_with("//! `demo` runtime\n"));
+        assert!(render(&ErrorFile, &templates).starts_with("//! `demo` errors\n"));
+        assert!(ReadmeFile
+            .contents(&templates)
+            .expect("Unable to render README.md!")
+            .is_none());
+        assert!(ErrorFile.manifest_patch(&templates).lints().is_empty());
+    }
+
+    #[test]
+    fn error_chain_declares_its_cfg() {
+        let patch = ErrorFile.manifest_patch(&templates());
+        let lints = patch.lints().get("rust").expect("No rust lints!");
+
+        assert_eq!(
+            lints
+                .get("unexpected_cfgs")
+                .expect("No unexpected_cfgs lint!")
+                .to_string(),
+            "{ level = \"warn\", check-cfg = [\"cfg(has_error_description_deprecated)\"] }"
+        );
+    }
+
+    #[test]
+    fn licenses_and_readme_go_to_the_root() {
+        let templates = templates();
+        let license = LicenseFile::new("LICENSE-MIT");
+        let readme = ReadmeFile;
+
+        assert_eq!(license.path(), "LICENSE-MIT");
+        assert_eq!(license.destination(), Destination::Root);
+        assert!(render(&license, &templates).starts_with(&format!(
+            "Copyright (c) {} demo developers\n",
+            license::current_year()
+        )));
+        assert_eq!(
+            license.manifest_patch(&templates).package_values(),
+            [("license".to_string(), "MIT OR Apache-2.0".to_string())]
+        );
+
+        assert_eq!(readme.path(), "README.md");
+        assert_eq!(readme.destination(), Destination::Root);
+        assert!(render(&readme, &templates).starts_with("# demo\n"));
+        assert_eq!(
+            readme.manifest_patch(&templates).package_values(),
+            [("readme".to_string(), "README.md".to_string())]
+        );
+    }
+
+    #[test]
+    fn builtin_generators_follow_the_layout() {
+        let templates = templates();
+        let paths = |layout| {
+            builtin(&templates, layout)
+                .iter()
+                .map(|generator| generator.path().to_string())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            paths(Layout::Default),
+            [
+                "src/main.rs",
+                "src/error.rs",
+                "src/run.rs",
+                "LICENSE-MIT",
+                "LICENSE-APACHE",
+                "README.md"
+            ]
+        );
+        assert_eq!(
+            paths(Layout::Split),
+            [
+                "src/main.rs",
+                "src/lib.rs",
+                "src/error.rs",
+                "src/run.rs",
+                "LICENSE-MIT",
+                "LICENSE-APACHE",
+                "README.md"
+            ]
+        );
+    }
+}
@@ -0,0 +1,346 @@
+// Copyright (c) 2017 cargo-cli developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! `cargo-cli` template packs.
+//!
+//! A template pack is a directory holding a `template.toml` manifest along with the mustache
+//! templates it references.  See [Template Packs](../index.html#template-packs) for the format.
+
+use error::{ErrorKind, Result};
+use manifest::{DepKind, DepTable, Dependency};
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
+use toml;
+use util::read_to_string;
+
+/// The name of the template pack manifest file.
+pub const PACK_MANIFEST: &str = "template.toml";
+
+/// A template pack loaded from a directory.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Pack {
+    /// A short description of the pack.
+    #[serde(default)]
+    description: Option<String>,
+    /// The variables used by the pack templates.
+    #[serde(default)]
+    variables: BTreeMap<String, Variable>,
+    /// The files produced by the pack.
+    #[serde(default)]
+    files: Vec<PackFile>,
+    /// The dependencies to add to the generated `Cargo.toml`.  The version of a registry
+    /// dependency bounds the latest version lookup and is its fallback.
+    #[serde(default)]
+    dependencies: BTreeMap<String, Dependency>,
+    /// The dev-dependencies to add to the generated `Cargo.toml`.
+    #[serde(default, rename = "dev-dependencies")]
+    dev_dependencies: BTreeMap<String, Dependency>,
+    /// The build-dependencies to add to the generated `Cargo.toml`.
+    #[serde(default, rename = "build-dependencies")]
+    build_dependencies: BTreeMap<String, Dependency>,
+    /// The platform specific dependencies to add to the generated `Cargo.toml`, keyed by target
+    /// triple or `cfg(...)` expression.
+    #[serde(default)]
+    target: BTreeMap<String, Platform>,
+    /// The directory the pack was loaded from.
+    #[serde(skip)]
+    dir: PathBuf,
+}
+
+/// The dependencies a template pack adds for a specific platform.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Platform {
+    /// The platform specific dependencies.
+    #[serde(default)]
+    dependencies: BTreeMap<String, Dependency>,
+    /// The platform specific dev-dependencies.
+    #[serde(default)]
+    dev_dependencies: BTreeMap<String, Dependency>,
+    /// The platform specific build-dependencies.
+    #[serde(default)]
+    build_dependencies: BTreeMap<String, Dependency>,
+}
+
+/// A variable used by the pack templates.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Variable {
+    /// A short description of the variable.
+    #[serde(default)]
+    description: Option<String>,
+    /// The value used when the variable isn't given on the command line.
+    #[serde(default)]
+    default: Option<String>,
+}
+
+/// A file produced by a template pack.
+#[derive(Clone, Debug, Deserialize)]
+pub struct PackFile {
+    /// The output path, relative to the project root.  This is rendered as a template.
+    path: String,
+    /// The source template, relative to the pack directory.
+    template: String,
+    /// Should the license header be prepended to the output?
+    #[serde(default)]
+    header: bool,
+    /// The variables the template requires.
+    #[serde(default)]
+    vars: Vec<String>,
+    /// The condition under which the file is emitted.
+    #[serde(default)]
+    when: Option<String>,
+}
+
+impl Pack {
+    /// Load the template pack in the given directory, if the directory holds a pack manifest.
+    pub fn load(dir: &Path) -> Result<Option<Pack>> {
+        let manifest_path = dir.join(PACK_MANIFEST);
+
+        if !manifest_path.is_file() {
+            return Ok(None);
+        }
+
+        let mut pack: Pack = toml::from_str(&read_to_string(&manifest_path)?)?;
+        for file in &pack.files {
+            check_path(&file.path)?;
+            check_path(&file.template)?;
+        }
+        pack.dir = dir.to_path_buf();
+        Ok(Some(pack))
+    }
+
+    /// Get the `description` value.
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    /// Get the `variables` value.
+    pub fn variables(&self) -> &BTreeMap<String, Variable> {
+        &self.variables
+    }
+
+    /// Get the `files` value.
+    pub fn files(&self) -> &[PackFile] {
+        &self.files
+    }
+
+    /// Get the non-empty dependency tables, i.e. `[dependencies]`, `[dev-dependencies]`,
+    /// `[build-dependencies]` and their `[target.<platform>]` counterparts.
+    pub fn dependency_tables(&self) -> Vec<(DepTable, &BTreeMap<String, Dependency>)> {
+        let mut tables = vec![
+            (DepTable::new(DepKind::Normal), &self.dependencies),
+            (DepTable::new(DepKind::Dev), &self.dev_dependencies),
+            (DepTable::new(DepKind::Build), &self.build_dependencies),
+        ];
+
+        for (target, platform) in &self.target {
+            tables.push((
+                DepTable::new(DepKind::Normal).target(target),
+                &platform.dependencies,
+            ));
+            tables.push((
+                DepTable::new(DepKind::Dev).target(target),
+                &platform.dev_dependencies,
+            ));
+            tables.push((
+                DepTable::new(DepKind::Build).target(target),
+                &platform.build_dependencies,
+            ));
+        }
+
+        tables.retain(|(_, deps)| !deps.is_empty());
+        tables
+    }
+
+    /// Read the source template for the given pack file.
+    pub fn source(&self, file: &PackFile) -> Result<String> {
+        let template_path = self.dir.join(&file.template);
+
+        if !template_path.is_file() {
+            return Err(ErrorKind::MissingTemplate(file.template.clone()).into());
+        }
+
+        // A symbolic link could still point outside of the pack.
+        if !template_path
+            .canonicalize()?
+            .starts_with(self.dir.canonicalize()?)
+        {
+            return Err(ErrorKind::InvalidTemplatePath(file.template.clone()).into());
+        }
+
+        read_to_string(&template_path)
+    }
+}
+
+impl Variable {
+    /// Get the `description` value.
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    /// Get the `default` value.
+    pub fn default(&self) -> Option<&str> {
+        self.default.as_deref()
+    }
+}
+
+impl PackFile {
+    /// Get the `path` value.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Get the `template` value.
+    pub fn template(&self) -> &str {
+        &self.template
+    }
+
+    /// Get the `header` value.
+    pub fn header(&self) -> bool {
+        self.header
+    }
+
+    /// Get the `vars` value.
+    pub fn vars(&self) -> &[String] {
+        &self.vars
+    }
+
+    /// Get the `when` value.
+    pub fn when(&self) -> Option<&str> {
+        self.when.as_deref()
+    }
+}
+
+/// Check that a pack file path stays inside the directory it is relative to, i.e. that it names
+/// a file, isn't absolute and holds no `..` component.
+pub fn check_path(path: &str) -> Result<()> {
+    let components = Path::new(path).components().collect::<Vec<_>>();
+    let is_name = |component: &Component| matches!(component, Component::Normal(_));
+
+    if components.iter().any(is_name)
+        && components
+            .iter()
+            .all(|component| is_name(component) || *component == Component::CurDir)
+    {
+        Ok(())
+    } else {
+        Err(ErrorKind::InvalidTemplatePath(path.to_string()).into())
+    }
+}
+
+/// Evaluate a `when` condition, looking up variable values with the given function.
+pub fn eval_condition<F>(condition: &str, lookup: F) -> Result<bool>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let condition = condition.trim();
+
+    if let Some(idx) = condition.find("!=") {
+        let (key, value) = (condition[..idx].trim(), condition[idx + 2..].trim());
+        Ok(lookup(key).is_none_or(|v| v != value))
+    } else if let Some(idx) = condition.find("==") {
+        let (key, value) = (condition[..idx].trim(), condition[idx + 2..].trim());
+        Ok(lookup(key).is_some_and(|v| v == value))
+    } else if let Some(key) = condition.strip_prefix('!') {
+        Ok(!is_truthy(lookup(key.trim())))
+    } else if condition.is_empty() || condition.contains(char::is_whitespace) {
+        Err(ErrorKind::InvalidCondition(condition.to_string()).into())
+    } else {
+        Ok(is_truthy(lookup(condition)))
+    }
+}
+
+/// Is the given variable value set, non-empty and not `false`?
+fn is_truthy(value: Option<String>) -> bool {
+    value.is_some_and(|v| !v.is_empty() && v != "false")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_path, eval_condition};
+    use error::ErrorKind;
+
+    fn lookup(key: &str) -> Option<String> {
+        match key {
+            "arg_parser" => Some("clap".to_string()),
+            "readme" => Some("true".to_string()),
+            "empty" => Some(String::new()),
+            "disabled" => Some("false".to_string()),
+            _ => None,
+        }
+    }
+
+    fn eval(condition: &str) -> bool {
+        eval_condition(condition, lookup).expect("Unable to evaluate the condition!")
+    }
+
+    #[test]
+    fn relative_paths_are_accepted() {
+        assert!(check_path("src/main.rs").is_ok());
+        assert!(check_path("./README.md").is_ok());
+        assert!(check_path("src/{{ name }}.rs").is_ok());
+    }
+
+    #[test]
+    fn escaping_paths_are_rejected() {
+        assert!(check_path("").is_err());
+        assert!(check_path(".").is_err());
+        assert!(check_path("/etc/hostname").is_err());
+        assert!(check_path("../ESCAPED-{{ name }}.txt").is_err());
+        assert!(check_path("src/../../main.rs").is_err());
+    }
+
+    #[test]
+    fn variables_are_truthy_when_set_and_not_false() {
+        assert!(eval("readme"));
+        assert!(eval(" arg_parser "));
+        assert!(!eval("empty"));
+        assert!(!eval("disabled"));
+        assert!(!eval("missing"));
+    }
+
+    #[test]
+    fn negated_conditions_are_inverted() {
+        assert!(!eval("!readme"));
+        assert!(eval("!empty"));
+        assert!(eval("!disabled"));
+        assert!(eval("! missing"));
+    }
+
+    #[test]
+    fn comparisons_match_the_value() {
+        assert!(eval("arg_parser == clap"));
+        assert!(eval("arg_parser==clap"));
+        assert!(!eval("arg_parser == docopt"));
+        assert!(!eval("missing == clap"));
+        assert!(eval("arg_parser != docopt"));
+        assert!(!eval("arg_parser != clap"));
+        assert!(eval("missing != clap"));
+    }
+
+    #[test]
+    fn combined_conditions_are_rejected() {
+        for condition in &[
+            "",
+            "  ",
+            "readme && arg_parser",
+            "readme || empty",
+            "not readme",
+        ] {
+            match *eval_condition(condition, lookup)
+                .expect_err("A combined condition was accepted!")
+                .kind()
+            {
+                ErrorKind::InvalidCondition(ref invalid) => {
+                    assert_eq!(invalid, condition.trim())
+                }
+                ref kind => panic!("unexpected error: {}", kind),
+            }
+        }
+    }
+}
@@ -0,0 +1,403 @@
+// Copyright (c) 2017 cargo-cli developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! `cargo-cli` native package creation.
+//!
+//! Creates the package directory, manifest, `src/main.rs` (or `src/lib.rs`) and version control
+//! setup the same way `cargo new` and `cargo init` do, for when `cargo` itself can't be run.
+
+use error::{Error, ErrorKind, Result};
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::iter;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::str::FromStr;
+use tmpl::Edition;
+use toml_edit::Array;
+use util::read_to_string;
+
+/// The `src/main.rs` of a new package.
+const MAIN_RS: &str = "fn main() {\n    println!(\"Hello, world!\");\n}\n";
+
+/// The `src/lib.rs` of a new library package.
+const LIB_RS: &str = r#"pub fn add(left: u64, right: u64) -> u64 {
+    left + right
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_works() {
+        let result = add(2, 2);
+        assert_eq!(result, 4);
+    }
+}
+"#;
+
+/// The keywords a package can't be named after, as its crate name would be unusable.
+const KEYWORDS: [&str; 51] = [
+    "Self", "abstract", "as", "async", "await", "become", "box", "break", "const", "continue",
+    "crate", "do", "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if",
+    "impl", "in", "let", "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub",
+    "ref", "return", "self", "static", "struct", "super", "trait", "true", "try", "type", "typeof",
+    "unsafe", "unsized", "use", "virtual", "where", "while",
+];
+
+/// The names of the standard library crates, which a package can't be named after.
+const STD_NAMES: [&str; 6] = ["alloc", "core", "proc-macro", "proc_macro", "std", "test"];
+
+/// The directories cargo puts build artifacts in, which a package can't be named after.
+const ARTIFACT_DIRS: [&str; 4] = ["build", "deps", "examples", "incremental"];
+
+/// The device names Windows reserves, which a package directory can't be named after.
+const WINDOWS_NAMES: [&str; 4] = ["aux", "con", "nul", "prn"];
+
+/// The version control system to initialize a package with.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Vcs {
+    /// git
+    Git,
+    /// Mercurial
+    Hg,
+    /// Pijul
+    Pijul,
+    /// Fossil
+    Fossil,
+    /// No version control
+    None,
+}
+
+impl FromStr for Vcs {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Vcs> {
+        match s {
+            "git" => Ok(Vcs::Git),
+            "hg" => Ok(Vcs::Hg),
+            "pijul" => Ok(Vcs::Pijul),
+            "fossil" => Ok(Vcs::Fossil),
+            "none" => Ok(Vcs::None),
+            _ => Err(ErrorKind::InvalidVcs(s.to_string()).into()),
+        }
+    }
+}
+
+impl fmt::Display for Vcs {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Vcs::Git => write!(f, "git"),
+            Vcs::Hg => write!(f, "hg"),
+            Vcs::Pijul => write!(f, "pijul"),
+            Vcs::Fossil => write!(f, "fossil"),
+            Vcs::None => write!(f, "none"),
+        }
+    }
+}
+
+/// A binary or library package to create.
+pub struct Package {
+    /// The package directory.
+    path: PathBuf,
+    /// The package name.
+    name: String,
+    /// The Rust edition of the package.
+    edition: Edition,
+    /// The version control system to initialize.
+    vcs: Vcs,
+    /// The registry the package may only be published to.
+    registry: Option<String>,
+    /// Is the package a library?
+    lib: bool,
+}
+
+impl Package {
+    /// Create a new git versioned binary package description, using the newest edition.
+    pub fn new(path: &Path, name: &str) -> Package {
+        Package {
+            path: path.to_path_buf(),
+            name: name.to_string(),
+            edition: Edition::newest(),
+            vcs: Vcs::Git,
+            registry: None,
+            lib: false,
+        }
+    }
+
+    /// Set the `edition` value.
+    pub fn set_edition(&mut self, edition: Edition) {
+        self.edition = edition;
+    }
+
+    /// Set the `vcs` value.
+    pub fn set_vcs(&mut self, vcs: Vcs) {
+        self.vcs = vcs;
+    }
+
+    /// Set the `registry` value.
+    pub fn set_registry(&mut self, registry: &str) {
+        self.registry = Some(registry.to_string());
+    }
+
+    /// Set the `lib` value.
+    pub fn set_lib(&mut self, lib: bool) {
+        self.lib = lib;
+    }
+
+    /// Create the package in a new directory, like `cargo new`.
+    pub fn create(&self) -> Result<()> {
+        if self.path.exists() {
+            return Err(ErrorKind::PackageExists(self.path.display().to_string()).into());
+        }
+        self.write()
+    }
+
+    /// Create the package in a directory that may already exist, like `cargo init`.
+    pub fn init(&self) -> Result<()> {
+        if self.path.join("Cargo.toml").exists() {
+            return Err(ErrorKind::PackageExists(self.path.display().to_string()).into());
+        }
+        self.write()
+    }
+
+    /// Write the package, keeping any `src/main.rs` (or `src/lib.rs`) already there.
+    fn write(&self) -> Result<()> {
+        check_name(&self.name)?;
+        fs::create_dir_all(self.path.join("src"))?;
+        init_vcs(&self.path, self.vcs)?;
+
+        let mut manifest = format!(
+            "[package]\nname = \"{}\"\nversion = \"0.1.0\"\nedition = \"{}\"\n",
+            self.name, self.edition
+        );
+        if let Some(ref registry) = self.registry {
+            let publish: Array = iter::once(registry.as_str()).collect();
+            manifest.push_str(&format!("publish = {}\n", publish));
+        }
+        manifest.push_str("\n[dependencies]\n");
+        write_new_file(&self.path.join("Cargo.toml"), &manifest)?;
+
+        let (source_path, source) = if self.lib {
+            (self.path.join("src").join("lib.rs"), LIB_RS)
+        } else {
+            (self.path.join("src").join("main.rs"), MAIN_RS)
+        };
+        if !source_path.exists() {
+            write_new_file(&source_path, source)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Initialize the version control repository in the given directory, unless there already is
+/// one, and ignore the `target` directory in it.
+pub fn init_vcs(path: &Path, vcs: Vcs) -> Result<()> {
+    match vcs {
+        Vcs::Git => {
+            if !path.join(".git").exists() {
+                vcs_command("git", &["init", "--quiet"], path)?;
+            }
+            ignore(&path.join(".gitignore"), "/target")
+        }
+        Vcs::Hg => {
+            if !path.join(".hg").exists() {
+                vcs_command("hg", &["init"], path)?;
+            }
+            ignore(&path.join(".hgignore"), "^target$")
+        }
+        Vcs::Pijul => {
+            if !path.join(".pijul").exists() {
+                vcs_command("pijul", &["init"], path)?;
+            }
+            ignore(&path.join(".ignore"), "/target")
+        }
+        Vcs::Fossil => {
+            let settings = path.join(".fossil-settings");
+            if !settings.exists() {
+                vcs_command("fossil", &["init", ".fossil"], path)?;
+                vcs_command("fossil", &["open", "--nested", ".fossil"], path)?;
+                fs::create_dir_all(&settings)?;
+            }
+            ignore(&settings.join("ignore-glob"), "target")?;
+            ignore(&settings.join("clean-glob"), "target")
+        }
+        Vcs::None => Ok(()),
+    }
+}
+
+/// Check the package name is usable as a crate name.
+fn check_name(name: &str) -> Result<()> {
+    let reason = if name.is_empty() {
+        Some("it is empty")
+    } else if name.starts_with(|c: char| c.is_ascii_digit()) {
+        Some("it starts with a digit")
+    } else if !name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        Some("only letters, numbers, `-` and `_` are allowed")
+    } else if KEYWORDS.contains(&name) {
+        Some("it is a Rust keyword")
+    } else if STD_NAMES.contains(&name) {
+        Some("it conflicts with a standard library crate")
+    } else if ARTIFACT_DIRS.contains(&name) {
+        Some("it conflicts with a cargo build directory")
+    } else if is_windows_name(name) {
+        Some("it is a reserved Windows file name")
+    } else {
+        None
+    };
+
+    match reason {
+        Some(reason) => {
+            Err(ErrorKind::InvalidPackageName(name.to_string(), reason.to_string()).into())
+        }
+        None => Ok(()),
+    }
+}
+
+/// Is the name one of the device names Windows reserves, i.e. `con`, `nul`, `com1` or `lpt9`?
+fn is_windows_name(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    let numbered = (name.starts_with("com") || name.starts_with("lpt"))
+        && name.len() == 4
+        && name.ends_with(|c: char| ('1'..='9').contains(&c));
+
+    WINDOWS_NAMES.contains(&name.as_str()) || numbered
+}
+
+/// Run a version control command in the given directory, failing with its output.
+fn vcs_command(program: &str, args: &[&str], dir: &Path) -> Result<()> {
+    let command = format!("{} {}", program, args.join(" "));
+    let output = Command::new(program)
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .map_err(|e| ErrorKind::VcsCommand(command.clone(), e.to_string()))?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        Err(ErrorKind::VcsCommand(command, stderr).into())
+    }
+}
+
+/// Add the pattern to the given ignore file, creating it if necessary.
+fn ignore(path: &Path, pattern: &str) -> Result<()> {
+    if !path.exists() {
+        return write_new_file(path, &format!("{}\n", pattern));
+    }
+
+    let contents = read_to_string(path)?;
+
+    if !contents.lines().any(|line| line.trim() == pattern) {
+        let separator = if contents.is_empty() || contents.ends_with('\n') {
+            ""
+        } else {
+            "\n"
+        };
+        let file = OpenOptions::new().append(true).open(path)?;
+        let mut file_writer = BufWriter::new(file);
+        file_writer.write_all(format!("{}{}\n", separator, pattern).as_bytes())?;
+    }
+
+    Ok(())
+}
+
+/// Write a file that must not exist yet.
+fn write_new_file(path: &Path, contents: &str) -> Result<()> {
+    let file = OpenOptions::new().create_new(true).write(true).open(path)?;
+    let mut file_writer = BufWriter::new(file);
+    file_writer.write_all(contents.as_bytes())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_name, Package, Vcs};
+    use error::ErrorKind;
+    use tempfile::TempDir;
+    use toml_edit::DocumentMut;
+    use util::read_to_string;
+
+    #[test]
+    fn registry_names_are_escaped() {
+        let dir = TempDir::new().expect("Unable to create the directory!");
+        let path = dir.path().join("demo");
+        let mut package = Package::new(&path, "demo");
+        package.set_vcs(Vcs::None);
+        package.set_registry("my \"quoted\" \\ registry");
+        package.create().expect("Unable to create the package!");
+
+        let manifest = read_to_string(&path.join("Cargo.toml")).expect("Unable to read!");
+        let doc = manifest
+            .parse::<DocumentMut>()
+            .expect("Unable to parse the manifest!");
+        let publish = doc["package"]["publish"]
+            .as_array()
+            .expect("No publish array!");
+        assert_eq!(publish.len(), 1);
+        assert_eq!(
+            publish.get(0).and_then(|v| v.as_str()),
+            Some("my \"quoted\" \\ registry")
+        );
+    }
+
+    /// Get the reason the given name is rejected.
+    fn rejection(name: &str) -> String {
+        match *check_name(name).unwrap_err().kind() {
+            ErrorKind::InvalidPackageName(ref invalid, ref reason) => {
+                assert_eq!(invalid, name);
+                reason.clone()
+            }
+            ref kind => panic!("unexpected error: {}", kind),
+        }
+    }
+
+    #[test]
+    fn crate_names_are_accepted() {
+        for name in &[
+            "cli", "my-cli", "my_cli2", "Tester", "testing", "builder", "com0", "coms",
+        ] {
+            assert!(check_name(name).is_ok(), "'{}' was rejected", name);
+        }
+    }
+
+    #[test]
+    fn unusable_names_are_rejected() {
+        assert_eq!(rejection(""), "it is empty");
+        assert_eq!(rejection("2cli"), "it starts with a digit");
+        assert_eq!(
+            rejection("my.cli"),
+            "only letters, numbers, `-` and `_` are allowed"
+        );
+        assert_eq!(rejection("fn"), "it is a Rust keyword");
+        assert_eq!(rejection("async"), "it is a Rust keyword");
+    }
+
+    #[test]
+    fn names_cargo_reserves_are_rejected() {
+        for name in &["test", "std", "core", "alloc", "proc_macro", "proc-macro"] {
+            assert_eq!(
+                rejection(name),
+                "it conflicts with a standard library crate"
+            );
+        }
+        for name in &["build", "deps", "examples", "incremental"] {
+            assert_eq!(rejection(name), "it conflicts with a cargo build directory");
+        }
+        for name in &["con", "nul", "aux", "prn", "CON", "com1", "lpt9"] {
+            assert_eq!(rejection(name), "it is a reserved Windows file name");
+        }
+    }
+}
@@ -0,0 +1,926 @@
+// Copyright (c) 2017 cargo-cli developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! `cargo-cli` dependency version lookup.
+//!
+//! Versions are queried from crates.io, or over the sparse index protocol from an alternate
+//! registry or crates.io mirror configured in `.cargo/config.toml`.  When the registry can't be
+//! reached or `--offline` is given, the lookup falls back to the `.crate` files downloaded to the
+//! local registry cache under `CARGO_HOME`, so that the chosen version builds offline.  The index
+//! cache cargo keeps for every crate it has resolved supplies their metadata.
+//!
+//! The newest stable, non-yanked release matching the declared version requirement is chosen
+//! unless pre-releases are allowed or a Rust version limits the choice, and the reason for each
+//! choice is reported at debug level.  The releases fetched from a registry are kept in the
+//! [`Cache`] for a day.
+
+use cache::Cache;
+use curl::easy::{Easy, List};
+use dirs;
+use error::{ErrorKind, Result};
+use semver::{Prerelease, Version, VersionReq};
+use serde_json;
+use std::env;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use toml;
+use util::read_to_string;
+
+/// json
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CrateInfo {
+    /// Crate data.
+    #[serde(rename = "crate")]
+    krate: Crate,
+    /// Every published version of the crate.
+    #[serde(default)]
+    versions: Vec<Release>,
+}
+
+impl fmt::Display for CrateInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "crate: {}, versions: {}",
+            self.krate,
+            self.versions.len()
+        )
+    }
+}
+
+/// Crate data
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Crate {
+    /// Maximum version field.
+    max_version: String,
+    /// Maximum stable version field.
+    #[serde(default)]
+    max_stable_version: Option<String>,
+}
+
+impl fmt::Display for Crate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "max_version: {}", self.max_version)
+    }
+}
+
+/// A published version of a crate, as listed by the crates.io web API or in a registry index.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Release {
+    /// The version.
+    #[serde(alias = "num")]
+    vers: String,
+    /// Has this version been yanked?
+    #[serde(default)]
+    yanked: bool,
+    /// The minimum supported Rust version, if declared.
+    #[serde(default)]
+    rust_version: Option<String>,
+}
+
+impl Release {
+    /// Create a release of the given version, not yanked and without a declared `rust-version`.
+    fn new(vers: &str) -> Release {
+        Release {
+            vers: vers.to_string(),
+            yanked: false,
+            rust_version: None,
+        }
+    }
+}
+
+/// How a version is chosen among the releases of a crate.
+#[derive(Clone, Debug, Default)]
+pub struct Selection {
+    /// May a pre-release be chosen?
+    allow_prerelease: bool,
+    /// The Rust version the chosen release must be compatible with.
+    rust_version: Option<Version>,
+    /// The version requirement the chosen release must match.
+    requirement: Option<VersionReq>,
+}
+
+impl Selection {
+    /// Allow pre-releases to be chosen.
+    pub fn set_allow_prerelease(&mut self, allow_prerelease: bool) {
+        self.allow_prerelease = allow_prerelease;
+    }
+
+    /// Only choose releases whose declared `rust-version` is compatible with the given Rust
+    /// version.
+    pub fn set_rust_version(&mut self, rust_version: Version) {
+        self.rust_version = Some(rust_version);
+    }
+
+    /// Only choose releases matching the given version requirement, e.g. the one declared for the
+    /// dependency.
+    pub fn set_requirement(&mut self, requirement: VersionReq) {
+        self.requirement = Some(requirement);
+    }
+
+    /// Does the given version match the version requirement?  When pre-releases are allowed,
+    /// those of a matching version match too.
+    fn matches(&self, version: &Version) -> bool {
+        match self.requirement {
+            Some(ref requirement) if self.allow_prerelease => {
+                let mut release = version.clone();
+                release.pre = Prerelease::EMPTY;
+                requirement.matches(&release)
+            }
+            Some(ref requirement) => requirement.matches(version),
+            None => true,
+        }
+    }
+
+    /// Get the reason the given release can't be chosen, if any.  Releases without a declared
+    /// `rust-version` are assumed to be compatible, as cargo does.
+    fn rejects(&self, version: &Version, release: &Release) -> Option<String> {
+        if release.yanked {
+            return Some("yanked".to_string());
+        }
+
+        if !self.allow_prerelease && !version.pre.is_empty() {
+            return Some("pre-release".to_string());
+        }
+
+        match (self.rust_version.as_ref(), release.rust_version.as_ref()) {
+            (Some(rust_version), Some(required)) => match parse_rust_version(required) {
+                Ok(ref required_version) if required_version > rust_version => {
+                    Some(format!("requires Rust {}", required))
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Describe the releases that may be chosen, e.g. `stable release matching ^4.0.0 compatible
+    /// with Rust 1.70`.
+    fn describe(&self) -> String {
+        let mut description = if self.allow_prerelease {
+            "release".to_string()
+        } else {
+            "stable release".to_string()
+        };
+
+        if let Some(ref requirement) = self.requirement {
+            description.push_str(&format!(" matching {}", requirement));
+        }
+
+        if let Some(ref rust_version) = self.rust_version {
+            description.push_str(&format!(" compatible with Rust {}", rust_version));
+        }
+
+        description
+    }
+}
+
+/// The version chosen for a dependency, and why it was chosen.
+#[derive(Clone, Debug)]
+pub struct Choice {
+    /// The chosen version.
+    version: String,
+    /// Why the version was chosen.
+    reason: String,
+}
+
+impl Choice {
+    /// Create a new choice of the given version.
+    pub fn new(version: &str, reason: &str) -> Choice {
+        Choice {
+            version: version.to_string(),
+            reason: reason.to_string(),
+        }
+    }
+
+    /// Get the `version` value.
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+
+    /// Get the `reason` value.
+    pub fn reason(&self) -> &str {
+        &self.reason
+    }
+}
+
+/// The registry the dependency versions are looked up in.
+#[derive(Clone, Debug, Default)]
+pub struct Registry {
+    /// The name of an alternate registry, or `None` for crates.io.
+    name: Option<String>,
+    /// The index URL, or `None` for crates.io itself, which is queried through its web API.
+    index: Option<String>,
+    /// The cache of earlier lookups, if it could be opened.
+    cache: Option<Cache>,
+}
+
+impl Registry {
+    /// Open the alternate registry with the given name, or crates.io when no name is given.  Any
+    /// `source` replacement of crates.io (i.e. a mirror) in the cargo configuration is honored.
+    pub fn open(name: Option<&str>) -> Result<Registry> {
+        Registry::configured(&CargoConfig::load()?, name)
+    }
+
+    /// Open the registry with the given name, or crates.io, as set up in the given configuration.
+    fn configured(config: &CargoConfig, name: Option<&str>) -> Result<Registry> {
+        if let Some(name) = name {
+            let index = config
+                .registry_index(name)
+                .ok_or_else(|| ErrorKind::UnknownRegistry(name.to_string()))?;
+
+            Ok(Registry {
+                name: Some(name.to_string()),
+                index: Some(index),
+                cache: Cache::open().ok(),
+            })
+        } else {
+            Ok(Registry {
+                name: None,
+                index: config.crates_io_replacement(),
+                cache: Cache::open().ok(),
+            })
+        }
+    }
+
+    /// Get the `name` value.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Choose the latest version of the given crate allowed by the selection, from a recent
+    /// lookup or the registry unless `offline` is set, falling back to the releases downloaded to
+    /// the local registry cache.
+    pub fn latest(&self, name: &str, offline: bool, selection: &Selection) -> Result<Choice> {
+        if !offline {
+            let cached = self
+                .cache
+                .as_ref()
+                .and_then(|cache| cache.get(&self.cache_key(), name));
+
+            if let Some(releases) = cached {
+                let source = format!("in the cached lookup on {}", self);
+                if let Ok(choice) = choose(name, releases, selection, &source) {
+                    return Ok(choice);
+                }
+            } else if let Some(Ok(releases)) = self.fetch_releases(name) {
+                if let Some(ref cache) = self.cache {
+                    // A lookup that can't be cached is simply made again next time.
+                    let _ = cache.put(&self.cache_key(), name, &releases);
+                }
+
+                if let Ok(choice) = choose(name, releases, selection, &format!("on {}", self)) {
+                    return Ok(choice);
+                }
+            }
+        }
+
+        let releases = local_releases(name, &self.cache_prefixes())?;
+        choose(name, releases, selection, "in the local registry cache")
+    }
+
+    /// Get the releases of the given crate from the registry, or `None` if the registry has a git
+    /// index, which is only read through the local registry cache.
+    fn fetch_releases(&self, name: &str) -> Option<Result<Vec<Release>>> {
+        match self.index {
+            None => Some(api_releases(name)),
+            Some(ref index) => index
+                .strip_prefix("sparse+")
+                .map(|url| sparse_releases(url, name)),
+        }
+    }
+
+    /// Get the name of the directory the lookups on this registry are cached in.
+    fn cache_key(&self) -> String {
+        match (self.name.as_ref(), self.index.as_ref()) {
+            (Some(name), _) => name.clone(),
+            (None, Some(index)) => format!("crates.io-{}", index_host(index)),
+            (None, None) => "crates.io".to_string(),
+        }
+    }
+
+    /// Get the prefixes of the directories cargo caches this registry in, i.e. the index host
+    /// followed by a hash of the index URL.
+    fn cache_prefixes(&self) -> Vec<String> {
+        match self.index {
+            None => vec!["index.crates.io-".to_string(), "github.com-".to_string()],
+            Some(ref index) => vec![format!("{}-", index_host(index))],
+        }
+    }
+}
+
+impl fmt::Display for Registry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (self.name.as_ref(), self.index.as_ref()) {
+            (Some(name), _) => write!(f, "the `{}` registry", name),
+            (None, Some(index)) => write!(f, "the crates.io mirror at {}", index),
+            (None, None) => write!(f, "crates.io"),
+        }
+    }
+}
+
+/// Get the releases of the given crate from the crates.io web API.
+fn api_releases(name: &str) -> Result<Vec<Release>> {
+    let crate_json = fetch(&format!("{}/api/v1/crates/{}", REGISTRY_HOST, name), name)?;
+    let crate_info: CrateInfo = serde_json::from_str(&crate_json)?;
+
+    if crate_info.versions.is_empty() {
+        // Without a version list, all there is to go on is the maximum (stable) version.
+        let krate = crate_info.krate;
+        let max_version = krate.max_stable_version.unwrap_or(krate.max_version);
+        Ok(vec![Release::new(&max_version)])
+    } else {
+        Ok(crate_info.versions)
+    }
+}
+
+/// Get the releases of the given crate from a sparse registry index.
+fn sparse_releases(url: &str, name: &str) -> Result<Vec<Release>> {
+    let url = format!("{}/{}", url.trim_end_matches('/'), index_path(name));
+    let index_file = fetch(&url, name)?;
+
+    Ok(index_file
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+/// Fetch the given URL for the given crate.
+fn fetch(url: &str, name: &str) -> Result<String> {
+    let mut easy = Easy::new();
+    easy.url(url)?;
+    easy.timeout(Duration::from_secs(5))?;
+    easy.get(true)?;
+    easy.useragent(concat!(
+        env!("CARGO_PKG_NAME"),
+        "/",
+        env!("CARGO_PKG_VERSION")
+    ))?;
+    let mut headers = List::new();
+    headers.append("Accept: application/json")?;
+    easy.http_headers(headers)?;
+    // An empty encoding asks for any compression curl can decode.
+    easy.accept_encoding("")?;
+
+    let mut html = Vec::new();
+    {
+        let mut transfer = easy.transfer();
+        transfer.write_function(|data| {
+            html.extend_from_slice(data);
+            Ok(data.len())
+        })?;
+
+        transfer.perform()?;
+    }
+
+    match easy.response_code()? {
+        200 => Ok(String::from_utf8(html)?),
+        404 | 410 | 451 => Err(ErrorKind::CrateNotFound(name.to_string()).into()),
+        code => Err(ErrorKind::RegistryRequest(url.to_string(), code).into()),
+    }
+}
+
+/// Get the releases of the given crate downloaded to the local registry cache, looking in the
+/// cache directories with the given prefixes.  Only these can be built offline, while the index
+/// cache may list releases that were never downloaded.
+fn local_releases(name: &str, prefixes: &[String]) -> Result<Vec<Release>> {
+    let registry_dir = cargo_home()?.join("registry");
+    let mut indexed = Vec::new();
+
+    for index_dir in cache_dirs(&registry_dir.join("index"), prefixes)? {
+        let cache_path = index_dir.join(".cache").join(index_path(name));
+        if cache_path.is_file() {
+            indexed.extend(read_index_cache(&cache_path)?);
+        }
+    }
+
+    let mut releases = Vec::new();
+    for cache_dir in cache_dirs(&registry_dir.join("cache"), prefixes)? {
+        for version in crate_file_versions(&cache_dir, name)? {
+            // The index cache entry, if any, knows whether the release was yanked and its
+            // `rust-version`.
+            let release = indexed
+                .iter()
+                .find(|release| release.vers == version)
+                .cloned()
+                .unwrap_or_else(|| Release::new(&version));
+            releases.push(release);
+        }
+    }
+
+    Ok(releases)
+}
+
+/// Choose the newest release allowed by the selection, noting where the releases were found
+/// (e.g. `on crates.io`) and the newer matching releases that were passed over in the reason.
+fn choose(
+    name: &str,
+    releases: Vec<Release>,
+    selection: &Selection,
+    source: &str,
+) -> Result<Choice> {
+    let mut versions: Vec<(Version, Release)> = releases
+        .into_iter()
+        .filter_map(|release| Version::parse(&release.vers).ok().map(|v| (v, release)))
+        .filter(|(version, _)| selection.matches(version))
+        .collect();
+    // The sort is stable, so the first copy of a version seen wins.
+    versions.sort_by(|a, b| b.0.cmp(&a.0));
+    versions.dedup_by(|a, b| a.0 == b.0);
+
+    let mut skipped = Vec::new();
+    for (version, release) in versions {
+        if let Some(why) = selection.rejects(&version, &release) {
+            skipped.push(format!("{} ({})", version, why));
+            continue;
+        }
+
+        let mut reason = format!("newest {} {}", selection.describe(), source);
+        if !skipped.is_empty() {
+            let more = skipped.len().saturating_sub(MAX_SKIPPED_SHOWN);
+            skipped.truncate(MAX_SKIPPED_SHOWN);
+            reason.push_str(&format!(", skipping {}", skipped.join(", ")));
+            if more > 0 {
+                reason.push_str(&format!(" and {} more", more));
+            }
+        }
+
+        return Ok(Choice::new(&version.to_string(), &reason));
+    }
+
+    Err(ErrorKind::CrateNotFound(name.to_string()).into())
+}
+
+/// Parse a `rust-version` value, i.e. `1.70` or `1.70.0`, into a comparable version.
+pub fn parse_rust_version(rust_version: &str) -> Result<Version> {
+    let parts = rust_version
+        .split('.')
+        .map(|part| part.parse::<u64>())
+        .collect::<::std::result::Result<Vec<u64>, _>>()
+        .map_err(|_| ErrorKind::InvalidRustVersion(rust_version.to_string()))?;
+
+    match parts[..] {
+        [major] => Ok(Version::new(major, 0, 0)),
+        [major, minor] => Ok(Version::new(major, minor, 0)),
+        [major, minor, patch] => Ok(Version::new(major, minor, patch)),
+        _ => Err(ErrorKind::InvalidRustVersion(rust_version.to_string()).into()),
+    }
+}
+
+/// The cargo configuration files, from the most to the least specific.
+struct CargoConfig {
+    /// The parsed configuration files.
+    tables: Vec<toml::Table>,
+}
+
+impl CargoConfig {
+    /// Load the `.cargo/config.toml` files in the current directory and its ancestors, followed
+    /// by the one in the cargo home directory.
+    fn load() -> Result<CargoConfig> {
+        CargoConfig::load_from(&env::current_dir()?, cargo_home().ok())
+    }
+
+    /// Load the `.cargo/config.toml` files in the given directory and its ancestors, followed by
+    /// the one in the given cargo home directory.
+    fn load_from(dir: &Path, cargo_home: Option<PathBuf>) -> Result<CargoConfig> {
+        let mut config_dirs: Vec<PathBuf> = dir.ancestors().map(|dir| dir.join(".cargo")).collect();
+        config_dirs.extend(cargo_home);
+
+        let mut tables = Vec::new();
+        for config_dir in config_dirs {
+            // The extensionless `config` is the legacy name, read only when `config.toml` is
+            // missing.
+            if let Some(config_path) = ["config.toml", "config"]
+                .iter()
+                .map(|file_name| config_dir.join(file_name))
+                .find(|path| path.is_file())
+            {
+                tables.push(toml::from_str(&read_to_string(&config_path)?)?);
+            }
+        }
+
+        Ok(CargoConfig { tables })
+    }
+
+    /// Get the most specific string value at the given key path.
+    fn get(&self, keys: &[&str]) -> Option<&str> {
+        self.tables.iter().find_map(|table| {
+            let (first, rest) = keys.split_first()?;
+            rest.iter()
+                .try_fold(table.get(*first)?, |value, key| value.get(*key))?
+                .as_str()
+        })
+    }
+
+    /// Get the index URL of the alternate registry with the given name, from the
+    /// `CARGO_REGISTRIES_<NAME>_INDEX` environment variable or the `[registries.<name>]` table.
+    fn registry_index(&self, name: &str) -> Option<String> {
+        let var = format!(
+            "CARGO_REGISTRIES_{}_INDEX",
+            name.to_uppercase().replace('-', "_")
+        );
+
+        env::var(var)
+            .ok()
+            .or_else(|| self.get(&["registries", name, "index"]).map(String::from))
+    }
+
+    /// Get the index URL crates.io is replaced with through `[source.crates-io] replace-with`,
+    /// if any.
+    fn crates_io_replacement(&self) -> Option<String> {
+        let mut source = CRATES_IO.to_string();
+
+        // Follow the chain of replacements, giving up on a cycle.
+        for _ in 0..MAX_REPLACEMENTS {
+            match self.get(&["source", &source, "replace-with"]) {
+                Some(replacement) => source = replacement.to_string(),
+                None => break,
+            }
+        }
+
+        if source == CRATES_IO {
+            None
+        } else {
+            self.get(&["source", &source, "registry"])
+                .map(String::from)
+                .or_else(|| self.registry_index(&source))
+        }
+    }
+}
+
+/// Get the cargo home directory, i.e. `$CARGO_HOME` or `~/.cargo`.
+fn cargo_home() -> Result<PathBuf> {
+    if let Some(cargo_home) = env::var_os("CARGO_HOME") {
+        Ok(PathBuf::from(cargo_home))
+    } else if let Some(home) = dirs::home_dir() {
+        Ok(home.join(".cargo"))
+    } else {
+        Err(ErrorKind::NoCargoHome.into())
+    }
+}
+
+/// Get the directories under the given registry directory whose names start with one of the
+/// given prefixes.
+fn cache_dirs(dir: &Path, prefixes: &[String]) -> Result<Vec<PathBuf>> {
+    let mut dirs = Vec::new();
+
+    if !dir.is_dir() {
+        return Ok(dirs);
+    }
+
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let dir_name = entry.file_name().to_string_lossy().into_owned();
+
+        if prefixes
+            .iter()
+            .any(|prefix| dir_name.starts_with(prefix.as_str()))
+        {
+            dirs.push(entry.path());
+        }
+    }
+
+    Ok(dirs)
+}
+
+/// Get the path of the given crate within a registry index, e.g. `se/rd/serde`.
+fn index_path(name: &str) -> String {
+    let name = name.to_lowercase();
+
+    match name.len() {
+        1 => format!("1/{}", name),
+        2 => format!("2/{}", name),
+        3 => format!("3/{}/{}", &name[..1], name),
+        _ => format!("{}/{}/{}", &name[..2], &name[2..4], name),
+    }
+}
+
+/// Get the host of an index URL, e.g. `index.crates.io` for `sparse+https://index.crates.io/`.
+fn index_host(index: &str) -> &str {
+    let url = index.split("://").nth(1).unwrap_or(index);
+    let authority = url.split('/').next().unwrap_or(url);
+    let host = authority.rsplit('@').next().unwrap_or(authority);
+    host.split(':').next().unwrap_or(host)
+}
+
+/// Read the entries of a cargo index cache file.
+///
+/// The file is a small binary header followed by NUL separated fields: the index revision, then
+/// pairs of version and JSON index entry.
+fn read_index_cache(path: &Path) -> Result<Vec<Release>> {
+    let mut contents = Vec::new();
+    let file = File::open(path)?;
+    let mut reader = BufReader::new(file);
+    reader.read_to_end(&mut contents)?;
+
+    Ok(contents
+        .split(|b| *b == 0)
+        .filter(|field| field.first() == Some(&b'{'))
+        .filter_map(|field| serde_json::from_slice(field).ok())
+        .collect())
+}
+
+/// Get the versions of the given crate downloaded to a registry cache directory, i.e. the
+/// `<name>-<version>.crate` files.
+fn crate_file_versions(dir: &Path, name: &str) -> Result<Vec<String>> {
+    let prefix = format!("{}-", name);
+    let mut versions = Vec::new();
+
+    for entry in fs::read_dir(dir)? {
+        let file_name = entry?.file_name().to_string_lossy().into_owned();
+
+        if let Some(version) = file_name
+            .strip_prefix(&prefix)
+            .and_then(|rest| rest.strip_suffix(".crate"))
+            .filter(|version| Version::parse(version).is_ok())
+        {
+            versions.push(version.to_string());
+        }
+    }
+
+    Ok(versions)
+}
+
+/// crates.io Cargo Registry
+const REGISTRY_HOST: &str = "https://crates.io";
+/// The name of crates.io in `[source]` replacement tables.
+const CRATES_IO: &str = "crates-io";
+/// The number of passed over releases listed in the reason for a choice.
+const MAX_SKIPPED_SHOWN: usize = 3;
+/// The longest chain of `[source]` replacements followed.
+const MAX_REPLACEMENTS: usize = 8;
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        choose, index_path, parse_rust_version, sparse_releases, CargoConfig, Registry, Release,
+        Selection,
+    };
+    use error::ErrorKind;
+    use semver::{Version, VersionReq};
+    use std::fs;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::sync::mpsc::{self, Receiver};
+    use std::thread;
+    use tempfile::TempDir;
+
+    /// The sparse index file of the `demo` crate, whose newest release is yanked.
+    const DEMO_INDEX: &str = r#"{"name":"demo","vers":"1.0.0","yanked":false}
+{"name":"demo","vers":"1.1.0","yanked":false,"rust_version":"1.70"}
+{"name":"demo","vers":"1.2.0","yanked":true}
+"#;
+
+    /// Serve the given files over HTTP on a local port, answering 404 for any other path.
+    /// Returns the base URL, and the headers of each request received.
+    fn serve(files: &'static [(&'static str, &'static str)]) -> (String, Receiver<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("Unable to bind the listener!");
+        let url = format!(
+            "http://{}",
+            listener.local_addr().expect("No local address!")
+        );
+
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.expect("Unable to accept the connection!");
+                let mut reader = BufReader::new(stream.try_clone().expect("Unable to clone!"));
+                let mut request_line = String::new();
+                reader
+                    .read_line(&mut request_line)
+                    .expect("Unable to read the request!");
+                let mut headers = Vec::new();
+                let mut header = String::new();
+                while reader.read_line(&mut header).is_ok() && header.trim() != "" {
+                    headers.push(header.trim().to_string());
+                    header.clear();
+                }
+                let _ = sender.send(headers);
+
+                let path = request_line.split(' ').nth(1).unwrap_or("");
+                let response = match files.iter().find(|&&(file_path, _)| file_path == path) {
+                    Some(&(_, body)) => format!(
+                        "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        body.len(),
+                        body
+                    ),
+                    None => {
+                        "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                            .to_string()
+                    }
+                };
+                let _ = stream.write_all(response.as_bytes());
+            }
+        });
+
+        (url, receiver)
+    }
+
+    /// Create a release of the given version, with the given `rust-version`.
+    fn release(vers: &str, yanked: bool, rust_version: Option<&str>) -> Release {
+        Release {
+            vers: vers.to_string(),
+            yanked,
+            rust_version: rust_version.map(str::to_string),
+        }
+    }
+
+    /// The releases of a crate with a pre-release, a yanked release and MSRV bumps.
+    fn releases() -> Vec<Release> {
+        vec![
+            release("1.0.0", false, None),
+            release("1.1.0", false, Some("1.60")),
+            release("1.2.0", false, Some("1.70.0")),
+            release("1.3.0", true, Some("1.70")),
+            release("2.0.0-beta.1", false, Some("1.74")),
+            release("not-a-version", false, None),
+        ]
+    }
+
+    #[test]
+    fn rust_versions_without_a_patch_are_zero_padded() {
+        let expected = Version::new(1, 70, 0);
+        assert_eq!(parse_rust_version("1.70").expect("Invalid!"), expected);
+        assert_eq!(parse_rust_version("1.70.0").expect("Invalid!"), expected);
+        assert_eq!(
+            parse_rust_version("1").expect("Invalid!"),
+            Version::new(1, 0, 0)
+        );
+        assert!(parse_rust_version("1.70.1").expect("Invalid!") > expected);
+    }
+
+    #[test]
+    fn invalid_rust_versions_are_rejected() {
+        for rust_version in &["", "1.70.0.1", "1.70-beta", "1.x", "v1.70"] {
+            match *parse_rust_version(rust_version).unwrap_err().kind() {
+                ErrorKind::InvalidRustVersion(ref invalid) => assert_eq!(invalid, rust_version),
+                ref kind => panic!("unexpected error: {}", kind),
+            }
+        }
+    }
+
+    #[test]
+    fn pre_releases_are_only_chosen_when_allowed() {
+        let stable = choose("demo", releases(), &Selection::default(), "here").expect("None!");
+        assert_eq!(stable.version(), "1.2.0");
+        assert_eq!(
+            stable.reason(),
+            "newest stable release here, skipping 2.0.0-beta.1 (pre-release), 1.3.0 (yanked)"
+        );
+
+        let mut selection = Selection::default();
+        selection.set_allow_prerelease(true);
+        let any = choose("demo", releases(), &selection, "here").expect("None!");
+        assert_eq!(any.version(), "2.0.0-beta.1");
+        assert_eq!(any.reason(), "newest release here");
+    }
+
+    #[test]
+    fn releases_requiring_a_newer_rust_are_skipped() {
+        let mut selection = Selection::default();
+        selection.set_rust_version(parse_rust_version("1.69").expect("Invalid!"));
+        let choice = choose("demo", releases(), &selection, "here").expect("None!");
+        assert_eq!(choice.version(), "1.1.0");
+        assert!(choice.reason().ends_with("1.2.0 (requires Rust 1.70.0)"));
+
+        // `1.70` and `1.70.0` are the same Rust version.
+        selection.set_rust_version(parse_rust_version("1.70").expect("Invalid!"));
+        let choice = choose("demo", releases(), &selection, "here").expect("None!");
+        assert_eq!(choice.version(), "1.2.0");
+    }
+
+    #[test]
+    fn releases_outside_the_requirement_are_ignored() {
+        let mut selection = Selection::default();
+        selection.set_requirement(VersionReq::parse("~1.1").expect("Invalid!"));
+        let choice = choose("demo", releases(), &selection, "here").expect("None!");
+        assert_eq!(choice.version(), "1.1.0");
+        assert_eq!(choice.reason(), "newest stable release matching ~1.1 here");
+
+        // A pre-release of a matching version matches once pre-releases are allowed.
+        selection.set_requirement(VersionReq::parse("2").expect("Invalid!"));
+        assert!(choose("demo", releases(), &selection, "here").is_err());
+        selection.set_allow_prerelease(true);
+        let choice = choose("demo", releases(), &selection, "here").expect("None!");
+        assert_eq!(choice.version(), "2.0.0-beta.1");
+
+        selection.set_requirement(VersionReq::parse("3").expect("Invalid!"));
+        match *choose("demo", releases(), &selection, "here")
+            .unwrap_err()
+            .kind()
+        {
+            ErrorKind::CrateNotFound(ref name) => assert_eq!(name, "demo"),
+            ref kind => panic!("unexpected error: {}", kind),
+        }
+    }
+
+    #[test]
+    fn index_paths_are_bucketed_by_name_length() {
+        assert_eq!(index_path("a"), "1/a");
+        assert_eq!(index_path("ab"), "2/ab");
+        assert_eq!(index_path("abc"), "3/a/abc");
+        assert_eq!(index_path("demo"), "de/mo/demo");
+        assert_eq!(index_path("Serde_JSON"), "se/rd/serde_json");
+    }
+
+    #[test]
+    fn sparse_index_releases_are_fetched() {
+        let (url, requests) = serve(&[("/index/de/mo/demo", DEMO_INDEX)]);
+        let releases = sparse_releases(&format!("{}/index/", url), "demo").expect("No releases!");
+
+        // The JSON media type is asked for with `Accept`, while `Accept-Encoding` only names
+        // compression codecs.
+        let headers = requests.recv().expect("No request was received!");
+        let header = |name: &str| {
+            headers
+                .iter()
+                .filter_map(|header| header.split_once(": "))
+                .find(|(key, _)| key.eq_ignore_ascii_case(name))
+                .map(|(_, value)| value.to_string())
+        };
+        assert_eq!(header("accept").as_deref(), Some("application/json"));
+        let encodings = header("accept-encoding").expect("No Accept-Encoding header!");
+        assert!(encodings.contains("gzip"), "{}", encodings);
+        assert!(!encodings.contains("json"), "{}", encodings);
+        assert!(header("user-agent").is_some_and(|agent| agent.starts_with("cargo-cli/")));
+
+        assert_eq!(releases.len(), 3);
+        assert_eq!(releases[1].rust_version.as_deref(), Some("1.70"));
+        assert!(releases[2].yanked);
+    }
+
+    #[test]
+    fn sparse_index_misses_are_not_found() {
+        let (url, _) = serve(&[]);
+        let err = sparse_releases(&format!("{}/index", url), "missing").unwrap_err();
+
+        match *err.kind() {
+            ErrorKind::CrateNotFound(ref name) => assert_eq!(name, "missing"),
+            ref kind => panic!("unexpected error: {}", kind),
+        }
+    }
+
+    #[test]
+    fn yanked_releases_are_skipped() {
+        let (url, _) = serve(&[("/index/de/mo/demo", DEMO_INDEX)]);
+        let releases = sparse_releases(&format!("{}/index", url), "demo").expect("No releases!");
+        let choice =
+            choose("demo", releases, &Selection::default(), "on the fixture").expect("No choice!");
+
+        assert_eq!(choice.version(), "1.1.0");
+        assert_eq!(
+            choice.reason(),
+            "newest stable release on the fixture, skipping 1.2.0 (yanked)"
+        );
+    }
+
+    #[test]
+    fn registries_are_selected_from_the_cargo_config() {
+        let (url, _) = serve(&[("/index/de/mo/demo", DEMO_INDEX)]);
+        let dir = TempDir::new().expect("Unable to create the directory!");
+        fs::create_dir(dir.path().join(".cargo")).expect("Unable to create .cargo!");
+        fs::write(
+            dir.path().join(".cargo").join("config.toml"),
+            format!(
+                "[registries.fixture-registry]\nindex = \"sparse+{0}/index/\"\n\n\
+                 [source.crates-io]\nreplace-with = \"mirror\"\n\n\
+                 [source.mirror]\nregistry = \"sparse+{0}/mirror/\"\n",
+                url
+            ),
+        )
+        .expect("Unable to write the config!");
+        let config = CargoConfig::load_from(dir.path(), None).expect("Unable to load!");
+
+        let registry =
+            Registry::configured(&config, Some("fixture-registry")).expect("No registry!");
+        assert_eq!(registry.name(), Some("fixture-registry"));
+        assert_eq!(registry.to_string(), "the `fixture-registry` registry");
+        let releases = registry
+            .fetch_releases("demo")
+            .expect("Not a sparse registry!")
+            .expect("No releases!");
+        assert_eq!(releases.len(), 3);
+
+        let mirror = Registry::configured(&config, None).expect("No mirror!");
+        assert_eq!(
+            mirror.to_string(),
+            format!("the crates.io mirror at sparse+{}/mirror/", url)
+        );
+
+        match *Registry::configured(&config, Some("unknown"))
+            .unwrap_err()
+            .kind()
+        {
+            ErrorKind::UnknownRegistry(ref name) => assert_eq!(name, "unknown"),
+            ref kind => panic!("unexpected error: {}", kind),
+        }
+    }
+}
@@ -8,41 +8,20 @@
 
 //! `cargo-cli` runtime.
 
-use clap::{App, AppSettings, Arg, SubCommand};
-use error::{ErrorKind, Result};
-use std::collections::BTreeMap;
+use cargo_cli::cache::Cache;
+use cargo_cli::error::{ErrorKind, Result};
+use cargo_cli::license::parse_licenses;
+use cargo_cli::package::Vcs;
+use cargo_cli::stage::Existing;
+use cargo_cli::store::Store;
+use cargo_cli::tmpl::{ArgParser, Edition, ErrorCrate, Layout};
+use cargo_cli::{Generator, ProjectSpec};
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
 use std::fmt;
-use std::fs::{File, OpenOptions};
-use std::io::{BufReader, BufWriter, Read, Write};
-use std::path::PathBuf;
-use std::process::{Command, Stdio};
+use std::io::{self, IsTerminal, Write};
+use std::path::Path;
+use std::time::Duration;
 use term;
-use tmpl::{TemplateType, Templates};
-use toml;
-
-/// A partial representation of the Cargo.toml config.
-#[derive(Clone, Debug, Deserialize, Serialize)]
-struct Config {
-    /// The package configuration section.
-    package: Package,
-    /// The dependencies list.
-    dependencies: Option<BTreeMap<String, String>>,
-}
-
-/// A partial representation of the Cargo.toml package config.
-#[derive(Clone, Debug, Deserialize, Serialize)]
-struct Package {
-    /// The package name.
-    name: String,
-    /// The package version.
-    version: String,
-    /// The list of authors.
-    authors: Vec<String>,
-    /// The licenses.
-    license: Option<String>,
-    /// The readme file.
-    readme: Option<String>,
-}
 
 /// output level
 #[derive(Eq, Ord, PartialEq, PartialOrd)]
@@ -72,113 +51,6 @@ impl fmt::Display for Level {
     }
 }
 
-/// Write to the given file based on the template type.
-fn write_file(
-    file: File,
-    template: &Templates,
-    template_type: &TemplateType,
-    level: &Level,
-) -> Result<()> {
-    let mut file_writer = BufWriter::new(file);
-
-    match *template_type {
-        TemplateType::Main => {
-            if template.has_license() {
-                file_writer.write_all(template.prefix()?.as_bytes())?;
-            }
-            file_writer.write_all(template.main()?.as_bytes())?;
-            debug("Updated", "src/main.rs", level)?;
-        }
-        TemplateType::Error => {
-            if template.has_license() {
-                file_writer.write_all(template.prefix()?.as_bytes())?;
-            }
-            file_writer.write_all(template.error()?.as_bytes())?;
-            debug("Updated", "src/error.rs", level)?;
-        }
-        TemplateType::Run => {
-            if template.has_license() {
-                file_writer.write_all(template.prefix()?.as_bytes())?;
-            }
-            file_writer.write_all(template.run()?.as_bytes())?;
-            debug("Updated", "src/run.rs", level)?;
-        }
-        TemplateType::Mit => {
-            if let Some(mit) = template.mit() {
-                file_writer.write_all(mit.as_bytes())?;
-                debug("Created", "LICENSE-MIT", level)?;
-            }
-        }
-        TemplateType::Apache => {
-            if let Some(apache) = template.apache() {
-                file_writer.write_all(apache.as_bytes())?;
-                debug("Created", "LICENSE-APACHE", level)?;
-            }
-        }
-        TemplateType::Readme => {
-            if let Some(Ok(readme)) = template.readme() {
-                file_writer.write_all(readme.as_bytes())?;
-                debug("Created", "README.md", level)?;
-            }
-        }
-    }
-
-    Ok(())
-}
-
-/// Update a pre-existing file based on the given template.
-fn update_file(
-    path: &str,
-    path_parts: &[&str],
-    template: &Templates,
-    template_type: &TemplateType,
-    level: &Level,
-) -> Result<()> {
-    let mut file_path = PathBuf::from(path);
-    for path_part in path_parts {
-        file_path.push(path_part);
-    }
-
-    let file = OpenOptions::new()
-        .truncate(true)
-        .write(true)
-        .open(file_path.as_path())?;
-
-    write_file(file, template, template_type, level)
-}
-
-/// Create a new file based on the given template.
-fn create_file(
-    path: &str,
-    path_parts: &[&str],
-    template: &Templates,
-    template_type: &TemplateType,
-    level: &Level,
-) -> Result<()> {
-    let mut file_path = PathBuf::from(path);
-    for path_part in path_parts {
-        file_path.push(path_part);
-    }
-
-    let create_file = match *template_type {
-        TemplateType::Mit => template.mit().is_some(),
-        TemplateType::Apache => template.apache().is_some(),
-        TemplateType::Readme => template.readme().is_some(),
-        _ => true,
-    };
-
-    if create_file {
-        let file = OpenOptions::new()
-            .create_new(true)
-            .write(true)
-            .open(file_path.as_path())?;
-
-        write_file(file, template, template_type, level)?;
-    }
-
-    Ok(())
-}
-
 /// Log a `cargo` formatted message to the terminal.
 fn log_message(verb: &str, message: &str) -> Result<()> {
     let mut t = term::stdout().ok_or(ErrorKind::TermCommand)?;
@@ -207,286 +79,613 @@ fn info(verb: &str, message: &str, level: &Level) -> Result<()> {
     Ok(())
 }
 
-/// Parse the args, and execute the generated commands.
-pub fn run() -> Result<i32> {
-    let matches = App::new(env!("CARGO_PKG_NAME"))
-        .version(env!("CARGO_PKG_VERSION"))
-        .author(env!("CARGO_PKG_AUTHORS"))
-        .about("Creates a Rust command line application")
-        .setting(AppSettings::GlobalVersion)
-        .setting(AppSettings::VersionlessSubcommands)
-        .setting(AppSettings::SubcommandRequiredElseHelp)
-        .subcommand(
-            SubCommand::with_name("cli")
-                .arg(
-                    Arg::with_name("vcs")
-                        .long("vcs")
-                        .value_name("VCS")
-                        .help(
-                            "Initialize a new repository for the given version control system
-                        or do not initialize any version control at all, overriding a
-                        global configuration.",
-                        )
-                        .possible_values(&["git", "hg", "pijul", "fossil", "none"])
-                        .default_value("git")
-                        .takes_value(true),
-                )
-                .arg(
-                    Arg::with_name("name")
-                        .long("name")
-                        .value_name("NAME")
-                        .help("Set the resulting package name, defaults to the value of <path>.")
-                        .takes_value(true),
-                )
-                .arg(
-                    Arg::with_name("color")
-                        .long("color")
-                        .value_name("WHEN")
-                        .help("Coloring")
-                        .possible_values(&["auto", "always", "never"])
-                        .default_value("auto")
-                        .takes_value(true),
-                )
-                .arg(
-                    Arg::with_name("frozen")
-                        .long("frozen")
-                        .conflicts_with("locked")
-                        .help("Require Cargo.lock and cache are up to date"),
-                )
-                .arg(
-                    Arg::with_name("locked")
-                        .long("locked")
-                        .help("Require Cargo.lock is up to date"),
-                )
-                .arg(
-                    Arg::with_name("verbose")
-                        .short("v")
-                        .multiple(true)
-                        .help("Use verbose output (-vv very verbose/build.rs output)"),
-                )
-                .arg(
-                    Arg::with_name("quiet")
-                        .short("q")
-                        .long("quiet")
-                        .conflicts_with("verbose")
-                        .help("No output printed to stdout"),
-                )
-                .arg(
-                    Arg::with_name("arg_parser")
-                        .long("arg_parser")
-                        .short("a")
-                        .value_name("PARSER")
-                        .default_value("clap")
-                        .possible_values(&["clap", "docopt"])
-                        .help("Specify the argument parser to use in the generated output."),
-                )
-                .arg(
-                    Arg::with_name("license")
-                        .long("license")
-                        .value_name("TYPE")
-                        .help("Specify licensing to include in the generated output.")
-                        .possible_values(&["both", "mit", "apache", "none"])
-                        .default_value("both")
-                        .takes_value(true),
-                )
-                .arg(
-                    Arg::with_name("no-readme")
-                        .long("no-readme")
-                        .help("Turn off README.md generation."),
-                )
-                .arg(
-                    Arg::with_name("no-latest")
-                        .long("no-latest")
-                        .help("Turn off the crates.io query for the latest version (use default)."),
-                )
-                .arg(Arg::with_name("path").takes_value(true).required(true)),
-        )
-        .get_matches();
-
-    if let Some(cli_matches) = matches.subcommand_matches("cli") {
-        let mut cargo_new_args = Vec::new();
-        cargo_new_args.push("new");
-        cargo_new_args.push("--bin");
-
-        if cli_matches.is_present("frozen") {
-            cargo_new_args.push("--frozen");
+/// Manage the installed template packs.
+fn templates(matches: &ArgMatches) -> Result<i32> {
+    let store = Store::open()?;
+    let level = Level::Info;
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+
+    match matches.subcommand() {
+        ("list", Some(_)) => {
+            for (name, pack) in store.list()? {
+                writeln!(out, "{:<24} {}", name, pack.description().unwrap_or(""))?;
+            }
         }
+        ("show", Some(show_matches)) => {
+            let name = show_matches.value_of("name").unwrap_or("");
+            let pack = store.get(name)?;
 
-        if cli_matches.is_present("locked") {
-            cargo_new_args.push("--locked");
-        }
+            writeln!(out, "{}", name)?;
+            if let Some(description) = pack.description() {
+                writeln!(out, "    {}", description)?;
+            }
 
-        let level = if cli_matches.is_present("quiet") {
-            cargo_new_args.push("--quiet");
-            Level::Warn
-        } else {
-            match cli_matches.occurrences_of("verbose") {
-                0 => Level::Info,
-                1 => {
-                    cargo_new_args.push("-v");
-                    Level::Debug
+            writeln!(out, "\nFiles:")?;
+            for file in pack.files() {
+                write!(out, "    {} <- {}", file.path(), file.template())?;
+                if let Some(when) = file.when() {
+                    write!(out, " (when {})", when)?;
                 }
-                2 | _ => {
-                    cargo_new_args.push("-vv");
-                    Level::Trace
+                if !file.vars().is_empty() {
+                    write!(out, " [vars: {}]", file.vars().join(", "))?;
                 }
+                writeln!(out)?;
             }
-        };
 
-        if let Some(color) = cli_matches.value_of("color") {
-            cargo_new_args.push("--color");
-            cargo_new_args.push(color);
+            writeln!(out, "\nVariables:")?;
+            for (key, var) in pack.variables() {
+                write!(out, "    {}", key)?;
+                if let Some(description) = var.description() {
+                    write!(out, ": {}", description)?;
+                }
+                if let Some(default) = var.default() {
+                    write!(out, " [default: {}]", default)?;
+                }
+                writeln!(out)?;
+            }
+
+            for (table, deps) in pack.dependency_tables() {
+                writeln!(out, "\n[{}]", table)?;
+                for (name, dep) in deps {
+                    writeln!(out, "    {} = {}", name, dep)?;
+                }
+            }
         }
+        ("install", Some(install_matches)) => {
+            let source = install_matches.value_of("source").unwrap_or("");
+            let name = store.install(
+                Path::new(source),
+                install_matches.value_of("name"),
+                install_matches.is_present("force"),
+            )?;
+            info("Installed", &format!("template pack `{}`", name), &level)?;
+        }
+        ("remove", Some(remove_matches)) => {
+            let name = remove_matches.value_of("name").unwrap_or("");
+            store.remove(name)?;
+            info("Removed", &format!("template pack `{}`", name), &level)?;
+        }
+        _ => return Err(ErrorKind::InvalidSubCommand.into()),
+    }
 
-        if let Some(vcs) = cli_matches.value_of("vcs") {
-            cargo_new_args.push("--vcs");
-            cargo_new_args.push(vcs);
+    Ok(0)
+}
+
+/// Inspect or clear the registry lookup cache.
+fn cache(matches: &ArgMatches) -> Result<i32> {
+    let cache = Cache::open()?;
+    let level = Level::Info;
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+
+    match matches.subcommand() {
+        ("list", Some(_)) => {
+            writeln!(out, "{}", cache.dir().display())?;
+            for entry in cache.list()? {
+                writeln!(
+                    out,
+                    "    {:<16} {:<24} {:>3} releases, {} old{}",
+                    entry.registry(),
+                    entry.name(),
+                    entry.releases(),
+                    format_age(entry.age()),
+                    if entry.is_expired() { " (expired)" } else { "" }
+                )?;
+            }
         }
+        ("clear", Some(clear_matches)) => {
+            let removed = cache.clear(clear_matches.is_present("expired"))?;
+            info("Removed", &format!("{} cached lookups", removed), &level)?;
+        }
+        _ => return Err(ErrorKind::InvalidSubCommand.into()),
+    }
 
-        let path = if let Some(path) = cli_matches.value_of("path") {
-            path
-        } else {
-            return Err(ErrorKind::InvalidPath.into());
-        };
+    Ok(0)
+}
 
-        let name = if let Some(name) = cli_matches.value_of("name") {
-            cargo_new_args.push("--name");
-            cargo_new_args.push(name);
-            cargo_new_args.push(path);
-            name
-        } else {
-            cargo_new_args.push(path);
-            path
-        };
+/// Format an age as days and hours, or hours and minutes when less than a day.
+fn format_age(age: Duration) -> String {
+    let minutes = age.as_secs() / 60;
+    let (days, hours, minutes) = (minutes / (24 * 60), minutes / 60 % 24, minutes % 60);
 
-        let readme = !cli_matches.is_present("no-readme");
-        let query = !cli_matches.is_present("no-latest");
+    if days > 0 {
+        format!("{}d {}h", days, hours)
+    } else {
+        format!("{}h {}m", hours, minutes)
+    }
+}
 
-        let (mit, apache) = if let Some(license) = cli_matches.value_of("license") {
-            match license {
-                "both" => (true, true),
-                "mit" => (true, false),
-                "apache" => (false, true),
-                "none" => (false, false),
-                _ => return Err(ErrorKind::InvalidLicense.into()),
-            }
-        } else {
-            return Err(ErrorKind::InvalidLicense.into());
-        };
+/// Ask what to do with a generated file that already exists, skipping it when there's no terminal
+/// to ask on.
+fn ask(file_path: &str) -> Result<Existing> {
+    let stdin = io::stdin();
+    if !stdin.is_terminal() {
+        return Ok(Existing::Skip);
+    }
 
-        let template = if let Some(arg_parser) = cli_matches.value_of("arg_parser") {
-            match arg_parser {
-                "clap" => Templates::new(name, true, mit, apache, readme, query),
-                "docopt" => Templates::new(name, false, mit, apache, readme, query),
-                _ => return Err(ErrorKind::InvalidArgParser.into()),
-            }
+    loop {
+        write!(
+            io::stderr(),
+            "{} already exists, [s]kip, [o]verwrite or [m]erge? ",
+            file_path
+        )?;
+        io::stderr().flush()?;
+
+        let mut answer = String::new();
+        if stdin.read_line(&mut answer)? == 0 {
+            return Ok(Existing::Skip);
+        }
+        match answer.trim() {
+            "s" | "skip" => return Ok(Existing::Skip),
+            "o" | "overwrite" => return Ok(Existing::Overwrite),
+            "m" | "merge" => return Ok(Existing::Merge),
+            _ => continue,
+        }
+    }
+}
+
+/// The arguments shared by the commands creating a project.
+fn project_args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    vec![
+        Arg::with_name("vcs")
+            .long("vcs")
+            .value_name("VCS")
+            .help(
+                "Initialize a new repository for the given version control system
+            or do not initialize any version control at all, overriding a
+            global configuration.",
+            )
+            .possible_values(&["git", "hg", "pijul", "fossil", "none"])
+            .default_value("git")
+            .takes_value(true),
+        Arg::with_name("edition")
+            .long("edition")
+            .value_name("YEAR")
+            .help("Set the Rust edition of the generated output.")
+            .possible_values(&["2015", "2018", "2021", "2024"])
+            .takes_value(true),
+        Arg::with_name("rust-version")
+            .long("rust-version")
+            .value_name("X.Y")
+            .help(
+                "Set the minimum supported Rust version of the generated output,
+            choosing dependency versions compatible with it.",
+            )
+            .takes_value(true),
+        Arg::with_name("name")
+            .long("name")
+            .value_name("NAME")
+            .help("Set the resulting package name, defaults to the value of <path>.")
+            .takes_value(true),
+        Arg::with_name("color")
+            .long("color")
+            .value_name("WHEN")
+            .help("Coloring")
+            .possible_values(&["auto", "always", "never"])
+            .default_value("auto")
+            .takes_value(true),
+        Arg::with_name("frozen")
+            .long("frozen")
+            .conflicts_with("locked")
+            .help("Require Cargo.lock and cache are up to date"),
+        Arg::with_name("locked")
+            .long("locked")
+            .help("Require Cargo.lock is up to date"),
+        Arg::with_name("native")
+            .long("native")
+            .help("Create the package without running cargo, for when it isn't available."),
+        Arg::with_name("offline").long("offline").help(
+            "Run without accessing the network, resolving the latest versions from
+            the local registry cache",
+        ),
+        Arg::with_name("allow-prerelease")
+            .long("allow-prerelease")
+            .help("Allow pre-release versions when querying for the latest version."),
+        Arg::with_name("registry")
+            .long("registry")
+            .value_name("REGISTRY")
+            .help(
+                "Look the latest versions up in the given alternate registry from
+            .cargo/config.toml, and depend on it.",
+            )
+            .takes_value(true),
+        Arg::with_name("verbose")
+            .short("v")
+            .multiple(true)
+            .help("Use verbose output (-vv very verbose/build.rs output)"),
+        Arg::with_name("quiet")
+            .short("q")
+            .long("quiet")
+            .conflicts_with("verbose")
+            .help("No output printed to stdout"),
+        Arg::with_name("arg_parser")
+            .long("arg_parser")
+            .short("a")
+            .value_name("PARSER")
+            .default_value("clap")
+            .possible_values(&[
+                "clap",
+                "clap-derive",
+                "docopt",
+                "lexopt",
+                "pico-args",
+                "argh",
+                "bpaf",
+            ])
+            .help("Specify the argument parser to use in the generated output."),
+        Arg::with_name("errors")
+            .long("errors")
+            .value_name("CRATE")
+            .default_value("error-chain")
+            .possible_values(&["error-chain", "anyhow", "thiserror", "snafu", "eyre", "std"])
+            .help("Specify the error handling crate to use in the generated output."),
+        Arg::with_name("layout")
+            .long("layout")
+            .value_name("LAYOUT")
+            .help(
+                "Choose the project layout, split being a workspace with a binary package
+            and a -core library package.",
+            )
+            .possible_values(&["default", "split"])
+            .default_value("default")
+            .takes_value(true),
+        Arg::with_name("license")
+            .long("license")
+            .value_name("SPDX")
+            .help(
+                "Specify licensing to include in the generated output, as SPDX identifiers
+            joined by OR, both (MIT OR Apache-2.0) or none.",
+            )
+            .default_value("both")
+            .takes_value(true),
+        Arg::with_name("template-dir")
+            .long("template-dir")
+            .value_name("DIR")
+            .help(
+                "Load templates from a directory mirroring the generated layout,
+            falling back to the built-in templates for any missing files.",
+            )
+            .takes_value(true),
+        Arg::with_name("template")
+            .long("template")
+            .value_name("NAME")
+            .conflicts_with("template-dir")
+            .help("Use the installed template pack with the given name.")
+            .takes_value(true),
+        Arg::with_name("var")
+            .long("var")
+            .value_name("KEY=VALUE")
+            .help("Set a template variable used by a template pack.")
+            .multiple(true)
+            .number_of_values(1)
+            .takes_value(true),
+        Arg::with_name("no-readme")
+            .long("no-readme")
+            .help("Turn off README.md generation."),
+        Arg::with_name("no-latest")
+            .long("no-latest")
+            .help("Turn off the crates.io query for the latest version (use default)."),
+    ]
+}
+
+/// Create the project, logging what `cargo` reported and the version chosen for each dependency,
+/// and write the generated stage, or only print it for a dry run.
+fn generate(
+    generator: &mut Generator,
+    existing_action: Option<Existing>,
+    dry_run: bool,
+    level: &Level,
+) -> Result<()> {
+    generator.create()?;
+    // The diagnostics of a successful `cargo` run are only of interest with `-v`.
+    if *level <= Level::Debug {
+        io::stderr().write_all(generator.diagnostics().as_bytes())?;
+    }
+
+    let mut stage = generator.generate()?;
+    for (table, dep, choice) in generator.resolved() {
+        let location = if table.is_normal() {
+            String::new()
         } else {
-            return Err(ErrorKind::InvalidArgParser.into());
+            format!(" in [{}]", table)
         };
+        let msg = format!(
+            "{} v{}{} ({})",
+            dep,
+            choice.version(),
+            location,
+            choice.reason()
+        );
+        debug("Resolved", &msg, level)?;
+    }
+    if let Some(member) = generator.member_added() {
+        debug(
+            "Adding",
+            &format!("`{}` as a workspace member", member),
+            level,
+        )?;
+    }
 
-        let mut cargo_new = Command::new("cargo")
-            .stdout(Stdio::null())
-            .stderr(Stdio::null())
-            .args(&cargo_new_args)
-            .spawn()?;
-        let ecode = cargo_new.wait()?;
-
-        if !ecode.success() {
-            if let Some(code) = ecode.code() {
-                return Ok(code);
-            } else {
-                return Err(ErrorKind::InvalidExitCode.into());
-            }
+    if dry_run {
+        let stdout = io::stdout();
+        stage.write_plan(&mut stdout.lock(), *level <= Level::Debug)
+    } else {
+        let resolved = stage.resolve(generator.existing(), |file_path| match existing_action {
+            Some(action) => Ok(action),
+            None => ask(file_path),
+        })?;
+        for (verb, file_path) in resolved {
+            info(verb, &file_path, level)?;
+        }
+        for (verb, file_path) in stage.commit()? {
+            debug(verb, &file_path, level)?;
         }
+        Ok(())
+    }
+}
 
-        update_file(
-            path,
-            &["src", "main.rs"],
-            &template,
-            &TemplateType::Main,
-            &level,
-        )?;
-        create_file(
-            path,
-            &["src", "error.rs"],
-            &template,
-            &TemplateType::Error,
-            &level,
-        )?;
-        create_file(
-            path,
-            &["src", "run.rs"],
-            &template,
-            &TemplateType::Run,
-            &level,
-        )?;
-        create_file(
-            path,
-            &["LICENSE-MIT"],
-            &template,
-            &TemplateType::Mit,
-            &level,
-        )?;
-        create_file(
-            path,
-            &["LICENSE-APACHE"],
-            &template,
-            &TemplateType::Apache,
-            &level,
-        )?;
-        create_file(
-            path,
-            &["README.md"],
-            &template,
-            &TemplateType::Readme,
-            &level,
-        )?;
+/// Create a project with `cargo new`, or in an existing directory with `cargo init`, and generate
+/// the command line application on top of it.
+fn create(matches: &ArgMatches, init: bool) -> Result<i32> {
+    let path = if let Some(path) = matches.value_of("path") {
+        path
+    } else {
+        return Err(ErrorKind::InvalidPath.into());
+    };
+    let mut spec = ProjectSpec::new(Path::new(path));
+
+    if matches.is_present("frozen") {
+        spec = spec.cargo_arg("--frozen");
+    }
+
+    if matches.is_present("locked") {
+        spec = spec.cargo_arg("--locked");
+    }
+
+    let level = if matches.is_present("quiet") {
+        spec = spec.cargo_arg("--quiet");
+        Level::Warn
+    } else {
+        match matches.occurrences_of("verbose") {
+            0 => Level::Info,
+            1 => {
+                spec = spec.cargo_arg("-v");
+                Level::Debug
+            }
+            _ => {
+                spec = spec.cargo_arg("-vv");
+                Level::Trace
+            }
+        }
+    };
 
-        let mut cargo_toml_path = PathBuf::from(path);
-        cargo_toml_path.push("Cargo.toml");
-        let mut cargo_toml_str = String::new();
-        let cargo_toml = File::open(cargo_toml_path.as_path())?;
-        let mut cargo_toml_reader = BufReader::new(cargo_toml);
-        cargo_toml_reader.read_to_string(&mut cargo_toml_str)?;
-
-        let mut config: Config = toml::from_str(&cargo_toml_str)?;
-        let mut pkg = config.package.clone();
-        let mut deps = if let Some(deps) = config.dependencies {
-            deps
+    if let Some(color) = matches.value_of("color") {
+        // `cargo` writes into a pipe, so it can't tell whether color would reach a terminal.
+        let color = if color == "auto" && io::stderr().is_terminal() {
+            "always"
         } else {
-            BTreeMap::new()
+            color
         };
+        spec = spec.cargo_arg("--color").cargo_arg(color);
+    }
+
+    if let Some(registry) = matches.value_of("registry") {
+        spec = spec.registry(registry);
+    }
 
-        template.add_deps(&mut deps);
+    if let Some(edition) = matches.value_of("edition") {
+        spec = spec.edition(edition.parse::<Edition>()?);
+    }
+
+    if let Some(rust_version) = matches.value_of("rust-version") {
+        spec = spec.rust_version(rust_version);
+    }
 
-        if readme {
-            pkg.readme = Some(template.cargo_toml_readme().to_string());
+    if let Some(name) = matches.value_of("name") {
+        spec = spec.name(name);
+    }
+
+    if let Some(vcs) = matches.value_of("vcs") {
+        spec = spec.vcs(vcs.parse::<Vcs>()?);
+    }
+
+    let layout = if let Some(layout) = matches.value_of("layout") {
+        layout.parse::<Layout>()?
+    } else {
+        Layout::Default
+    };
+
+    let existing_action = match matches.value_of("existing") {
+        Some("ask") | None => None,
+        Some(action) => Some(action.parse::<Existing>()?),
+    };
+
+    let licenses = if let Some(license) = matches.value_of("license") {
+        parse_licenses(license)?
+    } else {
+        return Err(ErrorKind::InvalidLicense(String::new()).into());
+    };
+
+    let arg_parser = if let Some(arg_parser) = matches.value_of("arg_parser") {
+        arg_parser.parse::<ArgParser>()?
+    } else {
+        return Err(ErrorKind::InvalidArgParser.into());
+    };
+
+    let errors = if let Some(errors) = matches.value_of("errors") {
+        errors.parse::<ErrorCrate>()?
+    } else {
+        return Err(ErrorKind::InvalidErrorCrate.into());
+    };
+
+    if let Some(template_dir) = matches.value_of("template-dir") {
+        spec = spec.template_dir(Path::new(template_dir));
+    } else if let Some(template_name) = matches.value_of("template") {
+        spec = spec.template_dir(&Store::open()?.path(template_name)?);
+    }
+
+    if let Some(vars) = matches.values_of("var") {
+        for var in vars {
+            let mut kv = var.splitn(2, '=');
+            match (kv.next(), kv.next()) {
+                (Some(key), Some(value)) if !key.is_empty() => spec = spec.var(key, value),
+                _ => return Err(ErrorKind::InvalidTemplateVar(var.to_string()).into()),
+            }
         }
+    }
 
-        if mit && apache {
-            pkg.license = Some(template.cargo_toml_both().to_string());
-        } else if mit {
-            pkg.license = Some(template.cargo_toml_mit().to_string());
-        } else if apache {
-            pkg.license = Some(template.cargo_toml_apache().to_string());
+    let dry_run = matches.is_present("dry-run");
+    let spec = spec
+        .arg_parser(arg_parser)
+        .errors(errors)
+        .licenses(&licenses)
+        .readme(!matches.is_present("no-readme"))
+        .layout(layout)
+        .query(!matches.is_present("no-latest"))
+        .offline(matches.is_present("offline"))
+        .allow_prerelease(matches.is_present("allow-prerelease"))
+        .native(matches.is_present("native"))
+        .init(init)
+        .dry_run(dry_run);
+
+    let mut generator = Generator::new(spec)?;
+    if let Err(e) = generate(&mut generator, existing_action, dry_run, &level) {
+        if generator.rollback()? {
+            debug("Removed", path, &level)?;
         }
+        return Err(e);
+    }
 
-        config.package = pkg;
-        config.dependencies = Some(deps);
+    let msg = match layout {
+        Layout::Default => format!("binary cli (application) `{}` project", generator.name()),
+        Layout::Split => format!("split cli (application) `{}` workspace", generator.name()),
+    };
+    if dry_run {
+        info("Planned", &format!("{}, nothing was written", msg), &level)?;
+    } else if init {
+        info("Initialized", &msg, &level)?;
+    } else {
+        info("Created", &msg, &level)?;
+    }
 
-        let new_cargo_toml = OpenOptions::new()
-            .truncate(true)
-            .write(true)
-            .open(cargo_toml_path.as_path())?;
-        let mut cargo_toml_writer = BufWriter::new(new_cargo_toml);
-        cargo_toml_writer.write_all(toml::to_string(&config)?.as_bytes())?;
+    Ok(0)
+}
 
-        debug("Updated", "Cargo.toml", &level)?;
+/// Parse the args, and execute the generated commands.
+pub fn run() -> Result<i32> {
+    let matches = App::new(env!("CARGO_PKG_NAME"))
+        .version(env!("CARGO_PKG_VERSION"))
+        .author(env!("CARGO_PKG_AUTHORS"))
+        .about("Creates a Rust command line application")
+        .setting(AppSettings::GlobalVersion)
+        .setting(AppSettings::VersionlessSubcommands)
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .subcommand(
+            SubCommand::with_name("cli")
+                .setting(AppSettings::SubcommandsNegateReqs)
+                .setting(AppSettings::ArgsNegateSubcommands)
+                .subcommand(
+                    SubCommand::with_name("cache")
+                        .about("Inspect or clear the registry lookup cache")
+                        .setting(AppSettings::SubcommandRequiredElseHelp)
+                        .subcommand(
+                            SubCommand::with_name("list")
+                                .about("List the cached lookups and their age"),
+                        )
+                        .subcommand(
+                            SubCommand::with_name("clear")
+                                .about("Remove the cached lookups")
+                                .arg(
+                                    Arg::with_name("expired")
+                                        .long("expired")
+                                        .help("Only remove the lookups older than a day"),
+                                ),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("init")
+                        .about("Create a command line application in an existing directory")
+                        .args(&project_args())
+                        .arg(
+                            Arg::with_name("existing")
+                                .long("existing")
+                                .value_name("ACTION")
+                                .help(
+                                    "Choose what happens to the generated files that already
+                                exist, asking about each one by default.",
+                                )
+                                .possible_values(&["ask", "skip", "overwrite", "merge"])
+                                .default_value("ask")
+                                .takes_value(true),
+                        )
+                        .arg(Arg::with_name("path").takes_value(true).default_value(".")),
+                )
+                .subcommand(
+                    SubCommand::with_name("templates")
+                        .about("Manage the installed template packs")
+                        .setting(AppSettings::SubcommandRequiredElseHelp)
+                        .subcommand(
+                            SubCommand::with_name("list")
+                                .about("List the installed template packs"),
+                        )
+                        .subcommand(
+                            SubCommand::with_name("show")
+                                .about("Show the files and variables a template pack produces")
+                                .arg(Arg::with_name("name").takes_value(true).required(true)),
+                        )
+                        .subcommand(
+                            SubCommand::with_name("install")
+                                .about(
+                                    "Install a template pack from a local path or a local git
+                                repository",
+                                )
+                                .arg(
+                                    Arg::with_name("name")
+                                        .long("name")
+                                        .value_name("NAME")
+                                        .help(
+                                            "Set the installed pack name, defaults to the name of
+                                        <source>.",
+                                        )
+                                        .takes_value(true),
+                                )
+                                .arg(
+                                    Arg::with_name("force")
+                                        .long("force")
+                                        .help("Replace an installed pack with the same name"),
+                                )
+                                .arg(Arg::with_name("source").takes_value(true).required(true)),
+                        )
+                        .subcommand(
+                            SubCommand::with_name("remove")
+                                .about("Remove an installed template pack")
+                                .arg(Arg::with_name("name").takes_value(true).required(true)),
+                        ),
+                )
+                .args(&project_args())
+                .arg(Arg::with_name("dry-run").long("dry-run").help(
+                    "Render the templates and resolve the versions, but only print the
+                        files and Cargo.toml changes instead of writing them.",
+                ))
+                .arg(Arg::with_name("path").takes_value(true).required(true)),
+        )
+        .get_matches();
 
-        let msg = format!("binary cli (application) `{}` project", name);
-        info("Created", &msg, &level)?;
+    if let Some(cli_matches) = matches.subcommand_matches("cli") {
+        if let Some(templates_matches) = cli_matches.subcommand_matches("templates") {
+            return templates(templates_matches);
+        }
+
+        if let Some(cache_matches) = cli_matches.subcommand_matches("cache") {
+            return cache(cache_matches);
+        }
+
+        if let Some(init_matches) = cli_matches.subcommand_matches("init") {
+            return create(init_matches, true);
+        }
 
-        Ok(0)
+        create(cli_matches, false)
     } else {
         Err(ErrorKind::InvalidSubCommand.into())
     }
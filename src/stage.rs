@@ -0,0 +1,591 @@
+// Copyright (c) 2017 cargo-cli developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! `cargo-cli` staged project output.
+//!
+//! Everything generated after `cargo new` (or `cargo init`) is rendered into a [`Stage`] first,
+//! and only written once all of it has rendered.  Should anything fail along the way, the
+//! [`Rollback`] prepared before `cargo` ran undoes it, so a failed run leaves nothing behind.  A
+//! dry run writes the plan of the stage out instead of committing it.
+
+use error::{Error, ErrorKind, Result};
+use similar::TextDiff;
+use std::collections::{BTreeMap, BTreeSet};
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use util::read_to_string;
+
+/// The files `cargo init` may create or change in the project directory.
+pub const CARGO_FILES: [&str; 6] = [
+    "Cargo.toml",
+    "src/main.rs",
+    "src/lib.rs",
+    ".gitignore",
+    ".hgignore",
+    ".ignore",
+];
+/// The directories `cargo init` may create in the project directory.
+const CARGO_DIRS: [&str; 5] = ["src", ".git", ".hg", ".pijul", ".fossil-settings"];
+
+/// What to do with a generated file that already exists in the project directory.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Existing {
+    /// Keep the existing file.
+    Skip,
+    /// Replace the existing file with the generated one.
+    Overwrite,
+    /// Keep both, separated by conflict markers to resolve by hand.  A `Cargo.toml` can't be
+    /// merged this way, as the markers would leave it invalid.
+    Merge,
+}
+
+impl FromStr for Existing {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Existing> {
+        match s {
+            "skip" => Ok(Existing::Skip),
+            "overwrite" => Ok(Existing::Overwrite),
+            "merge" => Ok(Existing::Merge),
+            _ => Err(ErrorKind::InvalidExisting(s.to_string()).into()),
+        }
+    }
+}
+
+/// A file staged for writing.
+struct StagedFile {
+    /// The path, relative to the project root.
+    path: String,
+    /// The file contents.
+    contents: String,
+    /// May an existing file be replaced?
+    replace: bool,
+}
+
+impl StagedFile {
+    /// Is the file a `Cargo.toml` manifest, whose changes are shown as a diff in a plan?
+    fn is_manifest(&self) -> bool {
+        Path::new(&self.path).file_name() == Some("Cargo.toml".as_ref())
+    }
+}
+
+/// The generated files, held in memory until they're written together.
+pub struct Stage {
+    /// The project root.
+    root: PathBuf,
+    /// The staged files, in the order they were staged.
+    files: Vec<StagedFile>,
+}
+
+impl Stage {
+    /// Create an empty stage for the project at the given root.
+    pub fn new(root: &Path) -> Stage {
+        Stage {
+            root: root.to_path_buf(),
+            files: Vec::new(),
+        }
+    }
+
+    /// Stage a new file, which must not exist when the stage is committed.
+    pub fn create(&mut self, path: &str, contents: String) {
+        self.stage(path, contents, false);
+    }
+
+    /// Stage a file, replacing any existing file.
+    pub fn replace(&mut self, path: &str, contents: String) {
+        self.stage(path, contents, true);
+    }
+
+    /// Get the staged files, as their path relative to the project root and contents, in the order
+    /// they were staged.
+    pub fn files(&self) -> Vec<(&str, &str)> {
+        self.files
+            .iter()
+            .map(|file| (file.path.as_str(), file.contents.as_str()))
+            .collect()
+    }
+
+    /// Will the given file exist once the stage is committed?
+    pub fn exists(&self, path: &str) -> bool {
+        self.files.iter().any(|file| file.path == path) || self.root.join(path).exists()
+    }
+
+    /// Decide what happens to the staged files that were in the project directory before `cargo
+    /// init` ran, using the given function to choose for each one that differs from the generated
+    /// file.  Returns the action taken (`Skipped`, `Merged`) and path of each file not simply
+    /// overwritten.
+    pub fn resolve<F>(
+        &mut self,
+        existing: &BTreeSet<String>,
+        mut choose: F,
+    ) -> Result<Vec<(&'static str, String)>>
+    where
+        F: FnMut(&str) -> Result<Existing>,
+    {
+        let mut resolved = Vec::new();
+        let mut files = Vec::new();
+
+        for mut file in self.files.drain(..) {
+            if !existing.contains(&file.path) {
+                files.push(file);
+                continue;
+            }
+
+            let current = read_to_string(&self.root.join(&file.path))?;
+            if current == file.contents {
+                continue;
+            }
+
+            match choose(&file.path)? {
+                Existing::Skip => resolved.push(("Skipped", file.path)),
+                Existing::Overwrite => {
+                    file.replace = true;
+                    files.push(file);
+                }
+                Existing::Merge if file.is_manifest() => {
+                    return Err(ErrorKind::ManifestMerge(file.path).into());
+                }
+                Existing::Merge => {
+                    let separator = if current.ends_with('\n') { "" } else { "\n" };
+                    file.contents = format!(
+                        "<<<<<<< existing\n{}{}=======\n{}>>>>>>> generated\n",
+                        current, separator, file.contents
+                    );
+                    file.replace = true;
+                    resolved.push(("Merged", file.path.clone()));
+                    files.push(file);
+                }
+            }
+        }
+
+        self.files = files;
+        Ok(resolved)
+    }
+
+    /// Write the plan of the stage: the file tree of the project once committed, the size (or
+    /// contents) of each staged file, and the changes to `Cargo.toml`.
+    pub fn write_plan<W: Write>(&self, out: &mut W, contents: bool) -> Result<()> {
+        let mut tree = Tree::default();
+        for file_path in existing_files(&self.root, &CARGO_FILES) {
+            tree.insert(&file_path);
+        }
+        // Files outside the project, such as a workspace manifest, are staged by absolute path.
+        for file in self
+            .files
+            .iter()
+            .filter(|file| !Path::new(&file.path).is_absolute())
+        {
+            tree.insert(&file.path);
+        }
+        writeln!(out, ".")?;
+        tree.write(out, "")?;
+
+        for file in self.files.iter().filter(|file| !file.is_manifest()) {
+            let status = if self.root.join(&file.path).exists() {
+                "updated"
+            } else {
+                "new"
+            };
+            writeln!(
+                out,
+                "\n{} ({}, {} bytes)",
+                file.path,
+                status,
+                file.contents.len()
+            )?;
+            if contents {
+                write!(out, "{}", file.contents)?;
+            }
+        }
+
+        for manifest in self.files.iter().filter(|file| file.is_manifest()) {
+            let manifest_path = self.root.join(&manifest.path);
+            let original = if manifest_path.exists() {
+                read_to_string(&manifest_path)?
+            } else {
+                String::new()
+            };
+            let diff = TextDiff::from_lines(&original, &manifest.contents);
+            writeln!(out)?;
+            write!(
+                out,
+                "{}",
+                diff.unified_diff().header(&manifest.path, &manifest.path)
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Write the staged files, creating any missing parent directories and leaving files that
+    /// wouldn't change untouched.  Should a write fail, the files and directories written before
+    /// it are restored or removed again.  Returns the action taken (`Created` or `Updated`) and
+    /// path of each file written.
+    pub fn commit(self) -> Result<Vec<(&'static str, String)>> {
+        let mut rollback = Rollback::empty();
+
+        match self.write(&mut rollback) {
+            Ok(written) => Ok(written),
+            Err(e) => {
+                rollback.run()?;
+                Err(e)
+            }
+        }
+    }
+
+    /// Write the staged files, recording how to undo each write in the given rollback.
+    fn write(&self, rollback: &mut Rollback) -> Result<Vec<(&'static str, String)>> {
+        let mut written = Vec::new();
+
+        for file in &self.files {
+            let full_path = self.root.join(&file.path);
+            if let Some(parent) = full_path.parent() {
+                if let Some(created) = parent.ancestors().take_while(|dir| !dir.exists()).last() {
+                    rollback.dirs.push(created.to_path_buf());
+                }
+                fs::create_dir_all(parent)?;
+            }
+
+            let (verb, previous) = if full_path.exists() {
+                let current = read_to_string(&full_path)?;
+                if file.replace && current == file.contents {
+                    continue;
+                }
+                ("Updated", Some(current))
+            } else {
+                ("Created", None)
+            };
+            rollback.files.push((full_path.clone(), previous));
+
+            let mut options = OpenOptions::new();
+            options.write(true);
+            if file.replace {
+                options.create(true).truncate(true);
+            } else {
+                options.create_new(true);
+            }
+
+            let mut file_writer = BufWriter::new(options.open(&full_path)?);
+            file_writer.write_all(file.contents.as_bytes())?;
+            file_writer.flush()?;
+            written.push((verb, file.path.clone()));
+        }
+
+        Ok(written)
+    }
+
+    /// Stage a file, superseding any earlier staging of the same path.
+    fn stage(&mut self, path: &str, contents: String, replace: bool) {
+        self.files.retain(|file| file.path != path);
+        self.files.push(StagedFile {
+            path: path.to_string(),
+            contents,
+            replace,
+        });
+    }
+}
+
+/// A directory in a file tree, holding its entries by name.
+#[derive(Default)]
+struct Tree(BTreeMap<String, Tree>);
+
+impl Tree {
+    /// Add the file at the given path, and the directories leading to it.
+    fn insert(&mut self, path: &str) {
+        let mut dir = self;
+        for part in path.split('/') {
+            dir = dir.0.entry(part.to_string()).or_default();
+        }
+    }
+
+    /// Write the tree in the style of `tree`, indenting each line with the given prefix.
+    fn write<W: Write>(&self, out: &mut W, indent: &str) -> Result<()> {
+        let mut entries = self.0.iter().peekable();
+
+        while let Some((name, entry)) = entries.next() {
+            let (branch, nested) = if entries.peek().is_some() {
+                ("├──", "│   ")
+            } else {
+                ("└──", "    ")
+            };
+            writeln!(out, "{}{} {}", indent, branch, name)?;
+            entry.write(out, &format!("{}{}", indent, nested))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// How to undo `cargo new` or `cargo init`: the directories and files they create, the files
+/// they change, and the manifests of any enclosing workspace, which they may add the package to.
+/// A stage commit that fails partway through is undone the same way.
+pub struct Rollback {
+    /// The outermost directory `cargo` creates.
+    created: Option<PathBuf>,
+    /// The files `cargo` may change, with their original contents, if any.
+    files: Vec<(PathBuf, Option<String>)>,
+    /// The directories in the project `cargo` may create.
+    dirs: Vec<PathBuf>,
+}
+
+impl Rollback {
+    /// Prepare to undo `cargo new` or `cargo init` creating a project at the given path.  This
+    /// must happen before `cargo` runs.
+    pub fn prepare(path: &Path) -> Result<Rollback> {
+        let path = env::current_dir()?.join(path);
+
+        // `cargo` creates any missing parent directories as well.
+        let created = path
+            .ancestors()
+            .take_while(|dir| !dir.exists())
+            .last()
+            .map(Path::to_path_buf);
+
+        let mut files = Vec::new();
+        for file_path in &CARGO_FILES {
+            let file_path = path.join(file_path);
+            let contents = if file_path.is_file() {
+                Some(read_to_string(&file_path)?)
+            } else {
+                None
+            };
+            files.push((file_path, contents));
+        }
+        for dir in path.ancestors().skip(1) {
+            let manifest_path = dir.join("Cargo.toml");
+            if manifest_path.is_file() {
+                let contents = read_to_string(&manifest_path)?;
+                files.push((manifest_path, Some(contents)));
+            }
+        }
+
+        let dirs = CARGO_DIRS
+            .iter()
+            .map(|dir| path.join(dir))
+            .filter(|dir| !dir.exists())
+            .collect();
+
+        Ok(Rollback {
+            created,
+            files,
+            dirs,
+        })
+    }
+
+    /// Create a rollback with nothing to undo yet.
+    fn empty() -> Rollback {
+        Rollback {
+            created: None,
+            files: Vec::new(),
+            dirs: Vec::new(),
+        }
+    }
+
+    /// Undo `cargo`, removing what it created and restoring any file it changed.
+    pub fn run(self) -> Result<()> {
+        if let Some(created) = self.created {
+            if created.exists() {
+                fs::remove_dir_all(created)?;
+            }
+        }
+
+        for (file_path, contents) in self.files {
+            match contents {
+                Some(contents) => {
+                    if read_to_string(&file_path)? != contents {
+                        let file = OpenOptions::new()
+                            .truncate(true)
+                            .write(true)
+                            .open(&file_path)?;
+                        let mut file_writer = BufWriter::new(file);
+                        file_writer.write_all(contents.as_bytes())?;
+                    }
+                }
+                None => {
+                    if file_path.exists() {
+                        fs::remove_file(&file_path)?;
+                    }
+                }
+            }
+        }
+
+        for dir in self.dirs {
+            if dir.exists() {
+                fs::remove_dir_all(dir)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Get the given paths, relative to the project at the given root, that are files in the project.
+/// Absolute paths, such as that of a workspace manifest, are outside of the project.
+pub fn existing_files<S: AsRef<str>>(root: &Path, paths: &[S]) -> BTreeSet<String> {
+    paths
+        .iter()
+        .map(AsRef::as_ref)
+        .filter(|path| !Path::new(path).is_absolute() && root.join(path).is_file())
+        .map(String::from)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Existing, Stage};
+    use error::ErrorKind;
+    use std::collections::{BTreeMap, BTreeSet};
+    use std::fs;
+    use std::path::Path;
+    use tempfile::TempDir;
+
+    /// Get the directories and files under the given directory, with the contents of each file.
+    fn snapshot(dir: &Path) -> BTreeMap<String, Option<String>> {
+        let mut entries = BTreeMap::new();
+        for entry in fs::read_dir(dir).expect("Unable to read the directory!") {
+            let path = entry.expect("Unable to read the entry!").path();
+            let name = path.display().to_string();
+            if path.is_dir() {
+                entries.extend(snapshot(&path));
+                entries.insert(name, None);
+            } else {
+                let contents = fs::read_to_string(&path).expect("Unable to read the file!");
+                entries.insert(name, Some(contents));
+            }
+        }
+        entries
+    }
+
+    #[test]
+    fn failed_commit_leaves_the_tree_unchanged() {
+        let root = TempDir::new().expect("Unable to create the directory!");
+        fs::create_dir(root.path().join("src")).expect("Unable to create src!");
+        fs::write(root.path().join("src/main.rs"), "fn main() {}\n").expect("No main.rs!");
+        fs::write(root.path().join("README.md"), "# existing\n").expect("No README.md!");
+        let before = snapshot(root.path());
+
+        let mut stage = Stage::new(root.path());
+        stage.replace("src/main.rs", "fn main() { run() }\n".to_string());
+        stage.create("src/run.rs", "fn run() {}\n".to_string());
+        stage.create("tests/cli/help.rs", "#[test]\nfn help() {}\n".to_string());
+        // README.md already exists, so creating it fails after the files above were written.
+        stage.create("README.md", "# generated\n".to_string());
+        stage.create("LICENSE-MIT", "MIT\n".to_string());
+
+        assert!(stage.commit().is_err());
+        assert_eq!(snapshot(root.path()), before);
+    }
+
+    #[test]
+    fn manifests_are_not_merged() {
+        let root = TempDir::new().expect("Unable to create the directory!");
+        fs::write(root.path().join("Cargo.toml"), "[package]\n").expect("No Cargo.toml!");
+        fs::write(root.path().join("README.md"), "# existing\n").expect("No README.md!");
+        let existing: BTreeSet<String> = ["Cargo.toml", "README.md"]
+            .iter()
+            .map(|path| path.to_string())
+            .collect();
+
+        let mut stage = Stage::new(root.path());
+        stage.replace("README.md", "# generated\n".to_string());
+        let resolved = stage
+            .resolve(&existing, |_| Ok(Existing::Merge))
+            .expect("Unable to merge README.md!");
+        assert_eq!(resolved, vec![("Merged", "README.md".to_string())]);
+        assert!(stage.files()[0]
+            .1
+            .starts_with("<<<<<<< existing\n# existing\n=======\n"));
+
+        stage.replace("Cargo.toml", "[package]\nname = \"cli\"\n".to_string());
+        match *stage
+            .resolve(&existing, |_| Ok(Existing::Merge))
+            .unwrap_err()
+            .kind()
+        {
+            ErrorKind::ManifestMerge(ref path) => assert_eq!(path, "Cargo.toml"),
+            ref kind => panic!("unexpected error: {}", kind),
+        }
+    }
+
+    #[test]
+    fn commit_reports_the_written_files() {
+        let root = TempDir::new().expect("Unable to create the directory!");
+        fs::write(root.path().join("Cargo.toml"), "[package]\n").expect("No Cargo.toml!");
+        fs::write(root.path().join("README.md"), "# same\n").expect("No README.md!");
+
+        let mut stage = Stage::new(root.path());
+        stage.replace("Cargo.toml", "[package]\nname = \"cli\"\n".to_string());
+        stage.replace("README.md", "# same\n".to_string());
+        stage.create("src/run.rs", "fn run() {}\n".to_string());
+
+        let written = stage.commit().expect("Unable to commit!");
+        assert_eq!(
+            written,
+            vec![
+                ("Updated", "Cargo.toml".to_string()),
+                ("Created", "src/run.rs".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn plans_are_written_without_touching_the_tree() {
+        let root = TempDir::new().expect("Unable to create the directory!");
+        fs::create_dir(root.path().join("src")).expect("Unable to create src!");
+        fs::write(root.path().join("src/main.rs"), "fn main() {}\n").expect("No main.rs!");
+        fs::write(
+            root.path().join("Cargo.toml"),
+            "[package]\nname = \"cli\"\n",
+        )
+        .expect("No Cargo.toml!");
+        let before = snapshot(root.path());
+
+        let mut stage = Stage::new(root.path());
+        stage.replace(
+            "Cargo.toml",
+            "[package]\nname = \"cli\"\nedition = \"2021\"\n".to_string(),
+        );
+        stage.replace("src/main.rs", "fn main() { run() }\n".to_string());
+        stage.create("src/run.rs", "fn run() {}\n".to_string());
+
+        let mut plan = Vec::new();
+        stage
+            .write_plan(&mut plan, false)
+            .expect("Unable to write the plan!");
+        assert_eq!(
+            String::from_utf8(plan).expect("The plan isn't UTF-8!"),
+            ".\n\
+             ├── Cargo.toml\n\
+             └── src\n    \
+             ├── main.rs\n    \
+             └── run.rs\n\
+             \n\
+             src/main.rs (updated, 20 bytes)\n\
+             \n\
+             src/run.rs (new, 12 bytes)\n\
+             \n\
+             --- Cargo.toml\n\
+             +++ Cargo.toml\n\
+             @@ -1,2 +1,3 @@\n \
+             [package]\n \
+             name = \"cli\"\n\
+             +edition = \"2021\"\n"
+        );
+
+        let mut plan = Vec::new();
+        stage
+            .write_plan(&mut plan, true)
+            .expect("Unable to write the plan!");
+        let plan = String::from_utf8(plan).expect("The plan isn't UTF-8!");
+        assert!(plan.contains("src/run.rs (new, 12 bytes)\nfn run() {}\n"));
+        assert_eq!(snapshot(root.path()), before);
+    }
+}
@@ -0,0 +1,215 @@
+// Copyright (c) 2017 cargo-cli developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! `cargo-cli` installed template packs.
+//!
+//! Template packs are installed one directory per pack under `cargo-cli/templates` in the user
+//! data directory, or under `$CARGO_CLI_HOME/templates` when that variable is set.
+
+use dirs;
+use error::{ErrorKind, Result};
+use pack::Pack;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// The environment variable overriding the `cargo-cli` data directory.
+pub const CARGO_CLI_HOME: &str = "CARGO_CLI_HOME";
+
+/// The installed template packs.
+pub struct Store {
+    /// The directory holding the installed packs.
+    dir: PathBuf,
+}
+
+impl Store {
+    /// Open the template pack store in the user data directory.
+    pub fn open() -> Result<Store> {
+        let mut dir = if let Some(home) = env::var_os(CARGO_CLI_HOME) {
+            PathBuf::from(home)
+        } else if let Some(data_dir) = dirs::data_dir() {
+            data_dir.join("cargo-cli")
+        } else {
+            return Err(ErrorKind::NoDataDir.into());
+        };
+        dir.push("templates");
+
+        Ok(Store { dir })
+    }
+
+    /// List the installed packs, sorted by name.
+    pub fn list(&self) -> Result<Vec<(String, Pack)>> {
+        let mut packs = Vec::new();
+
+        if !self.dir.is_dir() {
+            return Ok(packs);
+        }
+
+        for entry in fs::read_dir(&self.dir)? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+
+            if name.starts_with('.') {
+                continue;
+            }
+
+            if let Some(pack) = Pack::load(&entry.path())? {
+                packs.push((name, pack));
+            }
+        }
+
+        packs.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(packs)
+    }
+
+    /// Get the directory of the installed pack with the given name.
+    pub fn path(&self, name: &str) -> Result<PathBuf> {
+        validate_name(name)?;
+        let pack_dir = self.dir.join(name);
+
+        if pack_dir.is_dir() {
+            Ok(pack_dir)
+        } else {
+            Err(ErrorKind::UnknownTemplate(name.to_string()).into())
+        }
+    }
+
+    /// Get the installed pack with the given name.
+    pub fn get(&self, name: &str) -> Result<Pack> {
+        let pack_dir = self.path(name)?;
+        Pack::load(&pack_dir)?
+            .ok_or_else(|| ErrorKind::InvalidTemplatePack(pack_dir.display().to_string()).into())
+    }
+
+    /// Install the pack at `source`, either a pack directory or a local git repository holding
+    /// one.  The pack is named after the source directory unless `name` is given.  Returns the
+    /// name of the installed pack.
+    pub fn install(&self, source: &Path, name: Option<&str>, force: bool) -> Result<String> {
+        let name = match name {
+            Some(name) => name.to_string(),
+            None => default_name(source)?,
+        };
+        validate_name(&name)?;
+
+        let pack_dir = self.dir.join(&name);
+        if pack_dir.exists() && !force {
+            return Err(ErrorKind::TemplateExists(name).into());
+        }
+
+        fs::create_dir_all(&self.dir)?;
+        let staging = self.dir.join(format!(".{}.tmp", name));
+        if staging.exists() {
+            fs::remove_dir_all(&staging)?;
+        }
+
+        let staged = if is_git_repo(source) {
+            clone_repo(source, &staging)
+        } else if source.is_dir() {
+            copy_dir(source, &staging)
+        } else {
+            Err(ErrorKind::InvalidTemplatePack(source.display().to_string()).into())
+        }
+        .and_then(|_| match Pack::load(&staging)? {
+            Some(_) => Ok(()),
+            None => Err(ErrorKind::InvalidTemplatePack(source.display().to_string()).into()),
+        });
+
+        if let Err(e) = staged {
+            if staging.exists() {
+                fs::remove_dir_all(&staging)?;
+            }
+            return Err(e);
+        }
+
+        if pack_dir.exists() {
+            fs::remove_dir_all(&pack_dir)?;
+        }
+        fs::rename(&staging, &pack_dir)?;
+
+        Ok(name)
+    }
+
+    /// Remove the installed pack with the given name.
+    pub fn remove(&self, name: &str) -> Result<()> {
+        let pack_dir = self.path(name)?;
+        fs::remove_dir_all(pack_dir)?;
+        Ok(())
+    }
+}
+
+/// Make sure a pack name is a single, non-hidden path component.
+fn validate_name(name: &str) -> Result<()> {
+    if name.is_empty() || name.starts_with('.') || name.contains(['/', '\\']) {
+        Err(ErrorKind::InvalidTemplateName(name.to_string()).into())
+    } else {
+        Ok(())
+    }
+}
+
+/// The default pack name for the given source, i.e. the directory name without any `.git`
+/// extension.
+fn default_name(source: &Path) -> Result<String> {
+    let canonical = source.canonicalize()?;
+    let file_name = canonical
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .ok_or_else(|| ErrorKind::InvalidTemplatePack(source.display().to_string()))?;
+
+    Ok(file_name.trim_end_matches(".git").to_string())
+}
+
+/// Is the given directory a git work tree or a bare git repository?
+fn is_git_repo(path: &Path) -> bool {
+    path.join(".git").exists() || (path.join("HEAD").is_file() && path.join("objects").is_dir())
+}
+
+/// Clone the git repository at `source` into `dest`, dropping the repository metadata.
+fn clone_repo(source: &Path, dest: &Path) -> Result<()> {
+    let output = Command::new("git")
+        .arg("clone")
+        .arg("--quiet")
+        .arg(source)
+        .arg(dest)
+        .stdin(Stdio::null())
+        .output()?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        return Err(ErrorKind::GitClone(source.display().to_string(), stderr).into());
+    }
+
+    fs::remove_dir_all(dest.join(".git"))?;
+    Ok(())
+}
+
+/// Recursively copy the directory at `source` to `dest`, skipping any `.git` directory.  Symbolic
+/// links are refused, as they could pull files from outside of the pack into it.
+fn copy_dir(source: &Path, dest: &Path) -> Result<()> {
+    fs::create_dir_all(dest)?;
+
+    for entry in fs::read_dir(source)? {
+        let entry = entry?;
+        if entry.file_name() == ".git" {
+            continue;
+        }
+
+        let dest_path = dest.join(entry.file_name());
+        let file_type = entry.file_type()?;
+        if file_type.is_symlink() {
+            let path = entry.path().display().to_string();
+            return Err(ErrorKind::InvalidTemplatePath(path).into());
+        } else if file_type.is_dir() {
+            copy_dir(&entry.path(), &dest_path)?;
+        } else {
+            fs::copy(entry.path(), dest_path)?;
+        }
+    }
+
+    Ok(())
+}
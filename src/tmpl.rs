@@ -1,180 +1,624 @@
 //! `cargo-cli` template files
 
-use curl::easy::Easy;
-use error::Result;
+use error::{Error, ErrorKind, Result};
+use license::{self, License};
+use manifest::{DepKind, DepTable, Dependency};
 use mustache::{self, Data, MapBuilder};
-use serde_json;
+use pack::{check_path, eval_condition, Pack};
+use registry::{Choice, Registry, Selection};
+use semver::{Version, VersionReq};
+use std::borrow::Cow;
 use std::collections::BTreeMap;
 use std::fmt;
 use std::io::Cursor;
-use std::time::Duration;
-
-/// Template Type
-pub enum TemplateType {
-    /// main.rs
-    Main,
-    /// run.rs
-    Run,
-    /// error.rs
-    Error,
-    /// LICENSE-MIT
-    Mit,
-    /// LICENSE-APACHE
-    Apache,
-    /// README.md
-    Readme,
+use std::path::Path;
+use std::str::FromStr;
+use std::thread;
+use util::read_to_string;
+
+/// The argument parser used by the generated output.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ArgParser {
+    /// clap, using the builder API.
+    Clap,
+    /// clap, using the derive API.
+    ClapDerive,
+    /// docopt
+    Docopt,
+    /// lexopt
+    Lexopt,
+    /// pico-args
+    PicoArgs,
+    /// argh
+    Argh,
+    /// bpaf
+    Bpaf,
 }
 
-/// json
-#[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct CrateInfo {
-    /// Crate data.
-    #[serde(rename = "crate")]
-    krate: Crate,
+impl ArgParser {
+    /// Get the `run.rs` template for this argument parser.
+    fn run_source(self) -> &'static str {
+        match self {
+            ArgParser::Clap => CLAP_RUN_RS,
+            ArgParser::ClapDerive => CLAP_DERIVE_RUN_RS,
+            ArgParser::Docopt => DOCOPT_RUN_RS,
+            ArgParser::Lexopt => LEXOPT_RUN_RS,
+            ArgParser::PicoArgs => PICO_ARGS_RUN_RS,
+            ArgParser::Argh => ARGH_RUN_RS,
+            ArgParser::Bpaf => BPAF_RUN_RS,
+        }
+    }
+
+    /// Get the crates `main.rs` must declare for this argument parser, as (`#[macro_use]`
+    /// crates, plain crates).
+    fn crates(self) -> (&'static [&'static str], &'static [&'static str]) {
+        match self {
+            ArgParser::Clap | ArgParser::ClapDerive => (&[], &["clap"]),
+            ArgParser::Docopt => (&["serde_derive"], &["docopt"]),
+            ArgParser::Lexopt => (&[], &["lexopt"]),
+            ArgParser::PicoArgs => (&[], &["pico_args"]),
+            ArgParser::Argh => (&[], &["argh"]),
+            ArgParser::Bpaf => (&[], &["bpaf"]),
+        }
+    }
+
+    /// Get the argument parser errors `error.rs` must convert from, as (variant, type, crate).
+    fn foreign_errors(self) -> &'static [(&'static str, &'static str, &'static str)] {
+        match self {
+            ArgParser::Docopt => &[("Docopt", "::docopt::Error", "docopt")],
+            ArgParser::Lexopt => &[("Lexopt", "::lexopt::Error", "lexopt")],
+            ArgParser::PicoArgs => &[("PicoArgs", "::pico_args::Error", "pico-args")],
+            ArgParser::Clap | ArgParser::ClapDerive | ArgParser::Argh | ArgParser::Bpaf => &[],
+        }
+    }
+
+    /// Get the dependencies for this argument parser, as (name, default version, features).  The
+    /// default version is the requirement the template is written against, so the latest version
+    /// lookup stays within its major version, e.g. clap 2 for the `App` builder of `Clap`.
+    fn dependencies(self) -> &'static [(&'static str, &'static str, &'static [&'static str])] {
+        match self {
+            ArgParser::Clap => &[("clap", "2.25.0", &[])],
+            ArgParser::ClapDerive => &[("clap", "4.0.0", &["derive"])],
+            ArgParser::Docopt => &[
+                ("docopt", "0.8.1", &[]),
+                ("serde", "1.0.9", &[]),
+                ("serde_derive", "1.0.9", &[]),
+            ],
+            ArgParser::Lexopt => &[("lexopt", "0.3.0", &[])],
+            ArgParser::PicoArgs => &[("pico-args", "0.5.0", &[])],
+            ArgParser::Argh => &[("argh", "0.1.12", &[])],
+            ArgParser::Bpaf => &[("bpaf", "0.9.0", &[])],
+        }
+    }
+
+    /// Is this one of the clap flavors?
+    fn is_clap(self) -> bool {
+        self == ArgParser::Clap || self == ArgParser::ClapDerive
+    }
 }
 
-impl fmt::Display for CrateInfo {
+impl FromStr for ArgParser {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<ArgParser> {
+        match s {
+            "clap" => Ok(ArgParser::Clap),
+            "clap-derive" => Ok(ArgParser::ClapDerive),
+            "docopt" => Ok(ArgParser::Docopt),
+            "lexopt" => Ok(ArgParser::Lexopt),
+            "pico-args" => Ok(ArgParser::PicoArgs),
+            "argh" => Ok(ArgParser::Argh),
+            "bpaf" => Ok(ArgParser::Bpaf),
+            _ => Err(ErrorKind::InvalidArgParser.into()),
+        }
+    }
+}
+
+impl fmt::Display for ArgParser {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "crate: {}", self.krate)
+        match *self {
+            ArgParser::Clap => write!(f, "clap"),
+            ArgParser::ClapDerive => write!(f, "clap-derive"),
+            ArgParser::Docopt => write!(f, "docopt"),
+            ArgParser::Lexopt => write!(f, "lexopt"),
+            ArgParser::PicoArgs => write!(f, "pico-args"),
+            ArgParser::Argh => write!(f, "argh"),
+            ArgParser::Bpaf => write!(f, "bpaf"),
+        }
+    }
+}
+
+/// The error handling crate used by the generated output.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ErrorCrate {
+    /// error-chain
+    ErrorChain,
+    /// anyhow
+    Anyhow,
+    /// thiserror
+    Thiserror,
+    /// snafu
+    Snafu,
+    /// eyre
+    Eyre,
+    /// Plain `std::error::Error` trait objects.
+    Std,
+}
+
+impl ErrorCrate {
+    /// Get the `error.rs` template for this error crate.
+    fn error_source(self) -> &'static str {
+        match self {
+            ErrorCrate::ErrorChain => ERROR_CHAIN_ERROR_RS,
+            ErrorCrate::Anyhow => ANYHOW_ERROR_RS,
+            ErrorCrate::Thiserror => THISERROR_ERROR_RS,
+            ErrorCrate::Snafu => SNAFU_ERROR_RS,
+            ErrorCrate::Eyre => EYRE_ERROR_RS,
+            ErrorCrate::Std => STD_ERROR_RS,
+        }
+    }
+
+    /// Get the crates `main.rs` must declare for this error crate, as (`#[macro_use]` crates,
+    /// plain crates).
+    fn crates(self) -> (&'static [&'static str], &'static [&'static str]) {
+        match self {
+            ErrorCrate::ErrorChain => (&["error_chain"], &[]),
+            ErrorCrate::Anyhow => (&[], &["anyhow"]),
+            // thiserror's derive refers to `::core`, which 2015 edition crates must declare.
+            ErrorCrate::Thiserror => (&[], &["core", "thiserror"]),
+            ErrorCrate::Snafu => (&[], &["snafu"]),
+            ErrorCrate::Eyre => (&[], &["eyre"]),
+            ErrorCrate::Std => (&[], &[]),
+        }
+    }
+
+    /// Get the dependency name and default version for this error crate.  As for the argument
+    /// parsers, the default version bounds the latest version lookup.
+    fn dependency(self) -> Option<(&'static str, &'static str)> {
+        match self {
+            ErrorCrate::ErrorChain => Some(("error-chain", "0.12.4")),
+            ErrorCrate::Anyhow => Some(("anyhow", "1.0.0")),
+            ErrorCrate::Thiserror => Some(("thiserror", "2.0.0")),
+            ErrorCrate::Snafu => Some(("snafu", "0.8.0")),
+            ErrorCrate::Eyre => Some(("eyre", "0.6.0")),
+            ErrorCrate::Std => None,
+        }
+    }
+
+    /// Get the cfgs the macros of this error crate test, which the generated manifest declares
+    /// so the `unexpected_cfgs` lint doesn't warn about them.
+    fn check_cfgs(self) -> &'static [&'static str] {
+        match self {
+            ErrorCrate::ErrorChain => &["cfg(has_error_description_deprecated)"],
+            ErrorCrate::Anyhow
+            | ErrorCrate::Thiserror
+            | ErrorCrate::Snafu
+            | ErrorCrate::Eyre
+            | ErrorCrate::Std => &[],
+        }
+    }
+
+    /// Does `error.rs` declare a variant for each argument parser error it converts from?
+    fn has_foreign_variants(self) -> bool {
+        match self {
+            ErrorCrate::ErrorChain | ErrorCrate::Thiserror | ErrorCrate::Snafu => true,
+            ErrorCrate::Anyhow | ErrorCrate::Eyre | ErrorCrate::Std => false,
+        }
     }
 }
 
-/// Crate data
-#[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct Crate {
-    /// Maximum version field.
-    max_version: String,
+impl FromStr for ErrorCrate {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<ErrorCrate> {
+        match s {
+            "error-chain" => Ok(ErrorCrate::ErrorChain),
+            "anyhow" => Ok(ErrorCrate::Anyhow),
+            "thiserror" => Ok(ErrorCrate::Thiserror),
+            "snafu" => Ok(ErrorCrate::Snafu),
+            "eyre" => Ok(ErrorCrate::Eyre),
+            "std" => Ok(ErrorCrate::Std),
+            _ => Err(ErrorKind::InvalidErrorCrate.into()),
+        }
+    }
 }
 
-impl fmt::Display for Crate {
+impl fmt::Display for ErrorCrate {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "max_version: {}", self.max_version)
+        match *self {
+            ErrorCrate::ErrorChain => write!(f, "error-chain"),
+            ErrorCrate::Anyhow => write!(f, "anyhow"),
+            ErrorCrate::Thiserror => write!(f, "thiserror"),
+            ErrorCrate::Snafu => write!(f, "snafu"),
+            ErrorCrate::Eyre => write!(f, "eyre"),
+            ErrorCrate::Std => write!(f, "std"),
+        }
+    }
+}
+
+/// The Rust edition of the generated output.
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum Edition {
+    /// Rust 2015
+    Edition2015,
+    /// Rust 2018
+    Edition2018,
+    /// Rust 2021
+    Edition2021,
+    /// Rust 2024
+    Edition2024,
+}
+
+impl Edition {
+    /// Get the Rust version this edition was stabilized in.
+    pub fn rust_version(self) -> Version {
+        match self {
+            Edition::Edition2015 => Version::new(1, 0, 0),
+            Edition::Edition2018 => Version::new(1, 31, 0),
+            Edition::Edition2021 => Version::new(1, 56, 0),
+            Edition::Edition2024 => Version::new(1, 85, 0),
+        }
+    }
+
+    /// Get the newest edition, which `cargo new` defaults to.
+    pub fn newest() -> Edition {
+        Edition::Edition2024
+    }
+
+    /// Get the dependency resolver a package of this edition uses, which a virtual workspace
+    /// must name explicitly.
+    pub fn resolver(self) -> &'static str {
+        match self {
+            Edition::Edition2015 | Edition::Edition2018 => "1",
+            Edition::Edition2021 => "2",
+            Edition::Edition2024 => "3",
+        }
+    }
+
+    /// Get the newest edition supported by the given Rust version.
+    pub fn newest_for(rust_version: &Version) -> Edition {
+        *[
+            Edition::Edition2024,
+            Edition::Edition2021,
+            Edition::Edition2018,
+        ]
+        .iter()
+        .find(|edition| edition.rust_version() <= *rust_version)
+        .unwrap_or(&Edition::Edition2015)
+    }
+}
+
+impl FromStr for Edition {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Edition> {
+        match s {
+            "2015" => Ok(Edition::Edition2015),
+            "2018" => Ok(Edition::Edition2018),
+            "2021" => Ok(Edition::Edition2021),
+            "2024" => Ok(Edition::Edition2024),
+            _ => Err(ErrorKind::InvalidEdition(s.to_string()).into()),
+        }
+    }
+}
+
+impl fmt::Display for Edition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Edition::Edition2015 => write!(f, "2015"),
+            Edition::Edition2018 => write!(f, "2018"),
+            Edition::Edition2021 => write!(f, "2021"),
+            Edition::Edition2024 => write!(f, "2024"),
+        }
+    }
+}
+
+/// The layout of the generated project.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Layout {
+    /// A single binary package.
+    Default,
+    /// A workspace with a binary package for the argument parsing, and a `-core` library package
+    /// for the logic and error types.
+    Split,
+}
+
+impl FromStr for Layout {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Layout> {
+        match s {
+            "default" => Ok(Layout::Default),
+            "split" => Ok(Layout::Split),
+            _ => Err(ErrorKind::InvalidLayout(s.to_string()).into()),
+        }
     }
 }
 
 /// Contaier for file templates for various auto-generated files.
 pub struct Templates {
-    /// clap or docopt?
-    clap: bool,
-    /// mustache `Data`.
-    kvs: Data,
+    /// The argument parser used by the generated output.
+    arg_parser: ArgParser,
+    /// The error handling crate used by the generated output.
+    errors: ErrorCrate,
+    /// The Rust edition of the generated output.
+    edition: Edition,
+    /// The layout of the generated project.
+    layout: Layout,
+    /// The package name.
+    name: String,
+    /// Additional template variables.
+    vars: BTreeMap<String, String>,
+    /// The template pack, if one was loaded.
+    pack: Option<Pack>,
     /// The `main.rs` replacement.
-    main: &'static str,
+    main: Cow<'static, str>,
     /// The `run.rs` file.
-    run: &'static str,
+    run: Cow<'static, str>,
     /// The `error.rs` file.
-    error: &'static str,
+    error: Cow<'static, str>,
+    /// The `lib.rs` file of the split layout.
+    lib: Cow<'static, str>,
+    /// The licenses, of which the user may choose.
+    licenses: Vec<License>,
     /// The license prefix.
-    prefix: &'static str,
-    /// The `LICENSE-MIT` file.
-    mit: Option<&'static str>,
-    /// The `LICENSE-APACHE` file.
-    apache: Option<&'static str>,
+    prefix: String,
+    /// The year of the copyright lines.
+    year: u64,
+    /// The license files, by file name.
+    license_files: Vec<(&'static str, Cow<'static, str>)>,
     /// The README.md file.
-    readme: Option<&'static str>,
+    readme: Option<Cow<'static, str>>,
     /// Should we query for the latest version of the dependencies?
     query: bool,
+    /// Should the latest versions come from the local registry cache only?
+    offline: bool,
+    /// The registry the latest versions are looked up in.
+    registry: Registry,
+    /// How the latest versions are chosen.
+    selection: Selection,
 }
 
 impl Templates {
     /// Create a new template use for file creation.
     pub fn new(
         name: &str,
-        clap: bool,
-        mit: bool,
-        apache: bool,
+        arg_parser: ArgParser,
+        errors: ErrorCrate,
+        licenses: &[License],
         readme: bool,
         query: bool,
     ) -> Templates {
         let mut template = Templates {
-            clap,
-            kvs: MapBuilder::new().insert_str("name", name).build(),
-            main: "",
-            run: "",
-            error: "",
-            prefix: "",
-            mit: None,
-            apache: None,
+            arg_parser,
+            errors,
+            edition: Edition::Edition2015,
+            layout: Layout::Default,
+            name: name.to_string(),
+            vars: BTreeMap::new(),
+            pack: None,
+            main: Cow::Borrowed(""),
+            run: Cow::Borrowed(""),
+            error: Cow::Borrowed(""),
+            lib: Cow::Borrowed(""),
+            licenses: licenses.to_vec(),
+            prefix: String::new(),
+            year: license::current_year(),
+            license_files: Vec::new(),
             readme: None,
             query,
+            offline: false,
+            registry: Registry::default(),
+            selection: Selection::default(),
         };
 
-        if mit && apache {
-            template.prefix = PREFIX_BOTH;
-        }
-        if mit {
-            template.mit = Some(LICENSE_MIT);
-            if !apache {
-                template.prefix = PREFIX_MIT;
-            }
+        if let Some(notice) = license::notice(licenses) {
+            template.prefix = format!("{}//\n{}\n", COPYRIGHT, notice);
         }
-        if apache {
-            template.apache = Some(LICENSE_APACHE);
-            if !mit {
-                template.prefix = PREFIX_APACHE;
+        for license in licenses {
+            for (file_name, text) in license.files() {
+                if !template.has_license_file(file_name) {
+                    template
+                        .license_files
+                        .push((file_name, Cow::Borrowed(text)));
+                }
             }
         }
 
         if readme {
-            template.readme = Some(README);
+            template.readme = Some(Cow::Borrowed(README));
         }
 
-        if clap {
-            // Setup clap templates
-            template.main = CLAP_MAIN_RS;
-            template.run = CLAP_RUN_RS;
-            template.error = CLAP_ERROR_RS;
-        } else {
-            // Setup docopt templates
-            template.main = DOCOPT_MAIN_RS;
-            template.run = DOCOPT_RUN_RS;
-            template.error = DOCOPT_ERROR_RS;
-        }
+        template.main = Cow::Borrowed(MAIN_RS);
+        template.run = Cow::Borrowed(arg_parser.run_source());
+        template.error = Cow::Borrowed(errors.error_source());
+        template.lib = Cow::Borrowed(LIB_RS);
         template
     }
 
+    /// Replace the built-in templates with any matching files found in the given directory.
+    ///
+    /// If the directory holds a `template.toml` it is loaded as a template pack, which replaces
+    /// the built-in file set entirely.  Otherwise the directory mirrors the generated project
+    /// layout (`src/main.rs`, `src/run.rs`, `src/error.rs`, `src/lib.rs`, the license files such
+    /// as `LICENSE-MIT` and `README.md`), and anything it omits falls back to the built-in
+    /// template.
+    pub fn load_dir(&mut self, dir: &Path) -> Result<()> {
+        if !dir.is_dir() {
+            return Err(ErrorKind::InvalidTemplateDir(dir.display().to_string()).into());
+        }
+
+        if let Some(pack) = Pack::load(dir)? {
+            self.pack = Some(pack);
+            return Ok(());
+        }
+
+        if let Some(main) = read_template(dir, &["src", "main.rs"])? {
+            self.main = Cow::Owned(main);
+        }
+        if let Some(run) = read_template(dir, &["src", "run.rs"])? {
+            self.run = Cow::Owned(run);
+        }
+        if let Some(error) = read_template(dir, &["src", "error.rs"])? {
+            self.error = Cow::Owned(error);
+        }
+        if let Some(lib) = read_template(dir, &["src", "lib.rs"])? {
+            self.lib = Cow::Owned(lib);
+        }
+        for &mut (file_name, ref mut text) in &mut self.license_files {
+            if let Some(license_text) = read_template(dir, &[file_name])? {
+                *text = Cow::Owned(license_text);
+            }
+        }
+        if self.readme.is_some() {
+            if let Some(readme) = read_template(dir, &["README.md"])? {
+                self.readme = Some(Cow::Owned(readme));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Set the Rust edition the templates are rendered for.
+    pub fn set_edition(&mut self, edition: Edition) {
+        self.edition = edition;
+    }
+
+    /// Set the layout of the generated project.
+    pub fn set_layout(&mut self, layout: Layout) {
+        self.layout = layout;
+    }
+
+    /// Resolve the latest dependency versions from the local registry cache only, without
+    /// querying crates.io.
+    pub fn set_offline(&mut self, offline: bool) {
+        self.offline = offline;
+    }
+
+    /// Look the latest dependency versions up in the given registry.  Dependencies on an
+    /// alternate registry are declared with its name.
+    pub fn set_registry(&mut self, registry: Registry) {
+        self.registry = registry;
+    }
+
+    /// Only use dependency versions whose declared `rust-version` is compatible with the given
+    /// Rust version.
+    pub fn set_rust_version(&mut self, rust_version: Version) {
+        self.selection.set_rust_version(rust_version);
+    }
+
+    /// Allow pre-release dependency versions to be chosen.
+    pub fn set_allow_prerelease(&mut self, allow_prerelease: bool) {
+        self.selection.set_allow_prerelease(allow_prerelease);
+    }
+
+    /// Set a template variable.
+    pub fn set_var(&mut self, key: &str, value: &str) {
+        self.vars.insert(key.to_string(), value.to_string());
+    }
+
+    /// Does this set of templates come from a template pack?
+    pub fn has_pack(&self) -> bool {
+        self.pack.is_some()
+    }
+
+    /// Render the files of the template pack whose conditions hold, as (path, contents) pairs.
+    pub fn pack_files(&self) -> Result<Vec<(String, String)>> {
+        let mut files = Vec::new();
+
+        if let Some(ref pack) = self.pack {
+            for file in pack.files() {
+                if let Some(when) = file.when() {
+                    if !eval_condition(when, |key| self.lookup(key))? {
+                        continue;
+                    }
+                }
+
+                for var in file.vars() {
+                    if self.lookup(var).is_none() {
+                        return Err(ErrorKind::MissingTemplateVar(
+                            var.clone(),
+                            file.path().to_string(),
+                        )
+                        .into());
+                    }
+                }
+
+                let mut contents = String::new();
+                if file.header() && self.has_license() {
+                    contents.push_str(&self.prefix()?);
+                }
+                contents.push_str(&self.render(&pack.source(file)?)?);
+                let path = self.render(file.path())?;
+                check_path(&path)?;
+                files.push((path, contents));
+            }
+        }
+
+        Ok(files)
+    }
+
     /// Get the `main` value.
     pub fn main(&self) -> Result<String> {
-        self.render(self.main)
+        self.render(&self.main)
     }
 
     /// Get the `run` value.
     pub fn run(&self) -> Result<String> {
-        self.render(self.run)
+        self.render(&self.run)
     }
 
     /// Get the `error` value.
     pub fn error(&self) -> Result<String> {
-        self.render(self.error)
+        self.render(&self.error)
+    }
+
+    /// Get the `lib` value.
+    pub fn lib(&self) -> Result<String> {
+        self.render(&self.lib)
     }
 
     /// Get the `prefix` value.
     pub fn prefix(&self) -> Result<String> {
-        self.render(self.prefix)
+        self.render(&self.prefix)
+    }
+
+    /// Get the `licenses` value.
+    pub fn licenses(&self) -> &[License] {
+        &self.licenses
+    }
+
+    /// Get the names of the license files, in the order the licenses were given.
+    pub fn license_file_names(&self) -> Vec<&'static str> {
+        self.license_files
+            .iter()
+            .map(|&(file_name, _)| file_name)
+            .collect()
     }
 
-    /// Get the `mit` value.
-    pub fn mit(&self) -> Option<&str> {
-        self.mit
+    /// Render the license file with the given name, if it's one of the license files.
+    pub fn license_file(&self, file_name: &str) -> Option<Result<String>> {
+        self.license_files
+            .iter()
+            .find(|&&(name, _)| name == file_name)
+            .map(|(_, text)| self.render(text))
     }
 
-    /// Get the `apache` value.
-    pub fn apache(&self) -> Option<&str> {
-        self.apache
+    /// Is the license file with the given name one of the license files?
+    fn has_license_file(&self, file_name: &str) -> bool {
+        self.license_files
+            .iter()
+            .any(|&(name, _)| name == file_name)
     }
 
     /// Get the `readme` value.
     pub fn readme(&self) -> Option<Result<String>> {
-        if let Some(readme) = self.readme {
-            Some(self.render(readme))
-        } else {
-            None
-        }
+        self.readme.as_ref().map(|readme| self.render(readme))
     }
 
     /// Does this set of templates include license information?
     pub fn has_license(&self) -> bool {
-        self.mit.is_some() || self.apache.is_some()
+        !self.licenses.is_empty()
     }
 
     /// Get the readme value.
@@ -182,110 +626,328 @@ impl Templates {
         CARGO_TOML_README
     }
 
-    /// Get the license value for both.
-    pub fn cargo_toml_both(&self) -> &str {
-        CARGO_TOML_BOTH
+    /// Get the cfgs the error handling crate tests, which must be declared as expected.
+    pub fn check_cfgs(&self) -> &'static [&'static str] {
+        self.errors.check_cfgs()
     }
 
-    /// Get the license value for MIT only.
-    pub fn cargo_toml_mit(&self) -> &str {
-        CARGO_TOML_MIT
+    /// Get the license value, the `license` expression of the included licenses.
+    pub fn cargo_toml_license(&self) -> Option<String> {
+        license::expression(&self.licenses)
     }
 
-    /// Get the license value for APACHE-2.0 only.
-    pub fn cargo_toml_apache(&self) -> &str {
-        CARGO_TOML_APACHE
+    /// Add the appropriate deps to the deps `BTreeMap`, by dependency table, returning the version
+    /// chosen for each.  With the split layout, these are the deps of the binary package.
+    pub fn add_deps(
+        &self,
+        deps: &mut BTreeMap<DepTable, BTreeMap<String, Dependency>>,
+    ) -> Vec<(DepTable, String, Choice)> {
+        let mut wanted: Vec<(DepTable, String, Dependency)> = Vec::new();
+
+        if let Some(ref pack) = self.pack {
+            for (table, table_deps) in pack.dependency_tables() {
+                wanted.extend(
+                    table_deps
+                        .iter()
+                        .map(|(name, dep)| (table.clone(), name.clone(), dep.clone())),
+                );
+            }
+        } else {
+            let table = DepTable::new(DepKind::Normal);
+            if self.layout == Layout::Split {
+                let core = format!("{}-core", self.name);
+                let path = format!("../{}", core);
+                wanted.push((table.clone(), core, Dependency::new("0.1.0").path(&path)));
+            } else if let Some((error_crate, default)) = self.errors.dependency() {
+                wanted.push((
+                    table.clone(),
+                    error_crate.to_string(),
+                    Dependency::new(default),
+                ));
+            }
+            wanted.extend(self.arg_parser.dependencies().iter().map(
+                |&(name, default, features)| {
+                    (
+                        table.clone(),
+                        name.to_string(),
+                        Dependency::new(default).features(features),
+                    )
+                },
+            ));
+        }
+
+        self.resolve_deps(wanted, deps)
     }
 
-    /// Add the appropriate deps to the deps `BTreeMap`.
-    pub fn add_deps(&self, deps: &mut BTreeMap<String, String>) {
-        if self.clap {
-            let (error_chain_latest, clap_latest) = if self.query {
-                (
-                    get_latest("error-chain").unwrap_or_else(|_| "0.10.0".to_string()),
-                    get_latest("clap").unwrap_or_else(|_| "2.25.0".to_string()),
+    /// Add the deps of the `-core` library package of the split layout to the deps `BTreeMap`,
+    /// returning the version chosen for each.  Besides the error handling crate, these include
+    /// any argument parser whose errors `error.rs` declares a variant for.
+    pub fn add_core_deps(
+        &self,
+        deps: &mut BTreeMap<DepTable, BTreeMap<String, Dependency>>,
+    ) -> Vec<(DepTable, String, Choice)> {
+        let table = DepTable::new(DepKind::Normal);
+        let mut wanted: Vec<(DepTable, String, Dependency)> = Vec::new();
+
+        if let Some((error_crate, default)) = self.errors.dependency() {
+            wanted.push((
+                table.clone(),
+                error_crate.to_string(),
+                Dependency::new(default),
+            ));
+        }
+        if self.errors.has_foreign_variants() {
+            let foreign = self.arg_parser.foreign_errors();
+            wanted.extend(
+                self.arg_parser
+                    .dependencies()
+                    .iter()
+                    .filter(|&&(name, _, _)| foreign.iter().any(|&(_, _, krate)| krate == name))
+                    .map(|&(name, default, features)| {
+                        (
+                            table.clone(),
+                            name.to_string(),
+                            Dependency::new(default).features(features),
+                        )
+                    }),
+            );
+        }
+
+        self.resolve_deps(wanted, deps)
+    }
+
+    /// Look up the versions of the wanted deps and add them to the deps `BTreeMap`, returning the
+    /// version chosen for each.
+    fn resolve_deps(
+        &self,
+        wanted: Vec<(DepTable, String, Dependency)>,
+        deps: &mut BTreeMap<DepTable, BTreeMap<String, Dependency>>,
+    ) -> Vec<(DepTable, String, Choice)> {
+        // Each lookup may wait on the network, so make them all at once.  Path and git
+        // dependencies have nothing to look up.
+        let lookups: Vec<Option<Choice>> = thread::scope(|scope| {
+            let handles: Vec<_> = wanted
+                .iter()
+                .map(|(_, name, dep)| {
+                    if dep.is_registry() {
+                        let default = dep.version().unwrap_or("*");
+                        Some(scope.spawn(move || self.latest(dep.package_name(name), default)))
+                    } else {
+                        None
+                    }
+                })
+                .collect();
+
+            handles
+                .into_iter()
+                .map(|handle| {
+                    handle.map(|handle| handle.join().expect("Version lookup thread panicked!"))
+                })
+                .collect()
+        });
+
+        let mut choices = Vec::new();
+        for ((table, name, mut dep), choice) in wanted.into_iter().zip(lookups) {
+            if let Some(choice) = choice {
+                dep.set_version(choice.version());
+                if let Some(registry) = self.registry.name() {
+                    dep = dep.registry(registry);
+                }
+                choices.push((table.clone(), name.clone(), choice));
+            }
+
+            deps.entry(table).or_default().insert(name, dep);
+        }
+        choices
+    }
+
+    /// Choose the latest version of the given crate matching the declared version requirement,
+    /// or the declared version if the query is turned off or fails.
+    fn latest(&self, name: &str, default: &str) -> Choice {
+        if !self.query {
+            return Choice::new(default, "default, the version lookup is turned off");
+        }
+
+        let mut selection = self.selection.clone();
+        match VersionReq::parse(default) {
+            Ok(requirement) => selection.set_requirement(requirement),
+            Err(e) => {
+                return Choice::new(
+                    default,
+                    &format!("default, the requirement is invalid: {}", e),
                 )
+            }
+        }
+
+        self.registry
+            .latest(name, self.offline, &selection)
+            .unwrap_or_else(|e| Choice::new(default, &format!("default, the lookup failed: {}", e)))
+    }
+
+    /// Look up the value of a template variable.
+    ///
+    /// Besides `name` and any variables set on the command line, the flags `mit`, `apache`,
+    /// `license`, `readme`, `clap` and `docopt` and the values `arg_parser`, `errors`, `edition`
+    /// and `year` are always available.
+    fn lookup(&self, key: &str) -> Option<String> {
+        let flag = |set: bool| Some(set.to_string());
+
+        match key {
+            "name" => Some(self.name.clone()),
+            "year" => Some(self.year.to_string()),
+            "mit" => flag(self.licenses.contains(&License::Mit)),
+            "apache" => flag(self.licenses.contains(&License::Apache)),
+            "license" => flag(self.has_license()),
+            "readme" => flag(self.readme.is_some()),
+            "clap" => flag(self.arg_parser.is_clap()),
+            "docopt" => flag(self.arg_parser == ArgParser::Docopt),
+            "arg_parser" => Some(self.arg_parser.to_string()),
+            "errors" => Some(self.errors.to_string()),
+            "edition" => Some(self.edition.to_string()),
+            _ => self.vars.get(key).cloned().or_else(|| {
+                self.pack
+                    .as_ref()
+                    .and_then(|pack| pack.variables().get(key))
+                    .and_then(|var| var.default())
+                    .map(|default| default.to_string())
+            }),
+        }
+    }
+
+    /// Build the mustache `Data` used to render the templates.
+    fn kvs(&self) -> Data {
+        let edition_2015 = self.edition == Edition::Edition2015;
+        let split = self.layout == Layout::Split;
+        let core_crate = format!("{}_core", self.name.replace('-', "_"));
+        let foreign = self.arg_parser.foreign_errors();
+        let (mut macro_crates, mut crates): (Vec<&str>, Vec<&str>) = (Vec::new(), Vec::new());
+        let (mut core_macro_crates, mut core_crates): (Vec<&str>, Vec<&str>) =
+            (Vec::new(), Vec::new());
+
+        // Only 2015 edition crates need to declare their dependencies in `main.rs` (or `lib.rs`).
+        if edition_2015 {
+            let (parser_macro_crates, parser_crates) = self.arg_parser.crates();
+            let (error_macro_crates, error_crates) = self.errors.crates();
+            if split {
+                macro_crates.extend(parser_macro_crates);
+                crates.extend(parser_crates.iter().chain(&[core_crate.as_str()]));
+                core_macro_crates.extend(error_macro_crates);
+                core_crates.extend(error_crates);
+                if self.errors.has_foreign_variants() && !foreign.is_empty() {
+                    core_crates.extend(parser_crates);
+                }
             } else {
-                ("0.10.0".to_string(), "2.25.0".to_string())
-            };
-            deps.insert("error-chain".to_string(), error_chain_latest);
-            deps.insert("clap".to_string(), clap_latest);
-        } else {
-            let (error_chain_latest, docopt_latest, serde_derive_latest, serde_latest) =
-                if self.query {
-                    (
-                        get_latest("error-chain").unwrap_or_else(|_| "0.10.0".to_string()),
-                        get_latest("docopt").unwrap_or_else(|_| "0.8.1".to_string()),
-                        get_latest("serde_derive").unwrap_or_else(|_| "1.0.9".to_string()),
-                        get_latest("serde").unwrap_or_else(|_| "1.0.9".to_string()),
-                    )
-                } else {
-                    (
-                        "0.10.0".to_string(),
-                        "0.8.1".to_string(),
-                        "1.0.9".to_string(),
-                        "1.0.9".to_string(),
-                    )
-                };
-            deps.insert("serde_derive".to_string(), serde_derive_latest);
-            deps.insert("serde".to_string(), serde_latest);
-            deps.insert("error-chain".to_string(), error_chain_latest);
-            deps.insert("docopt".to_string(), docopt_latest);
+                macro_crates.extend(error_macro_crates.iter().chain(parser_macro_crates));
+                crates.extend(parser_crates.iter().chain(error_crates));
+            }
+        }
+
+        let mut builder = MapBuilder::new()
+            .insert_str("name", &self.name)
+            .insert_str("year", self.year.to_string())
+            .insert_bool("mit", self.licenses.contains(&License::Mit))
+            .insert_bool("apache", self.licenses.contains(&License::Apache))
+            .insert_bool("license", self.has_license())
+            .insert_bool("readme", self.readme.is_some())
+            .insert_bool("clap", self.arg_parser.is_clap())
+            .insert_bool("docopt", self.arg_parser == ArgParser::Docopt)
+            .insert_str("arg_parser", self.arg_parser.to_string())
+            .insert_str("errors", self.errors.to_string())
+            .insert_str("edition", self.edition.to_string())
+            .insert_bool("edition_2015", edition_2015)
+            .insert_str("crate_root", if edition_2015 { "" } else { "crate::" })
+            .insert_bool("split", split)
+            .insert_str("core_crate", &core_crate)
+            .insert_vec("macro_crates", |builder| {
+                macro_crates
+                    .iter()
+                    .fold(builder, |builder, krate| builder.push_str(krate))
+            })
+            .insert_vec("crates", |builder| {
+                crates
+                    .iter()
+                    .fold(builder, |builder, krate| builder.push_str(krate))
+            })
+            .insert_vec("core_macro_crates", |builder| {
+                core_macro_crates
+                    .iter()
+                    .fold(builder, |builder, krate| builder.push_str(krate))
+            })
+            .insert_vec("core_crates", |builder| {
+                core_crates
+                    .iter()
+                    .fold(builder, |builder, krate| builder.push_str(krate))
+            })
+            .insert_vec("foreign", |builder| {
+                foreign
+                    .iter()
+                    .fold(builder, |builder, &(variant, path, krate)| {
+                        builder.push_map(|map| {
+                            map.insert_str("variant", variant)
+                                .insert_str("path", path)
+                                .insert_str("krate", krate)
+                        })
+                    })
+            });
+
+        if let Some(ref pack) = self.pack {
+            for (key, var) in pack.variables() {
+                if let Some(default) = var.default() {
+                    builder = builder.insert_str(key.as_str(), default);
+                }
+            }
         }
+
+        for (key, value) in &self.vars {
+            builder = builder.insert_str(key.as_str(), value.as_str());
+        }
+
+        builder.build()
     }
 
-    /// Render the given mustache template with the key/value pairs in `kvs`.
+    /// Render the given mustache template with the template variables.
     fn render(&self, template_str: &str) -> Result<String> {
         let template = mustache::compile_str(template_str)?;
         let mut out = Cursor::new(Vec::new());
-        template.render_data(&mut out, &self.kvs)?;
+        template.render_data(&mut out, &self.kvs())?;
         Ok(String::from_utf8(out.into_inner())?)
     }
 }
 
-/// Get the latest version from crates.io.
-fn get_latest(name: &str) -> Result<String> {
-    let crate_json = fetch_cratesio(name)?;
-    let crate_info: CrateInfo = serde_json::from_str(&crate_json)?;
-    Ok(crate_info.krate.max_version)
-}
-
-/// Fetch crate data from crates.io.
-fn fetch_cratesio(path: &str) -> Result<String> {
-    let mut easy = Easy::new();
-    easy.url(&format!("{}/api/v1/crates/{}", REGISTRY_HOST, path))?;
-    easy.timeout(Duration::from_secs(5))?;
-    easy.get(true)?;
-    easy.accept_encoding("application/json")?;
-
-    let mut html = Vec::new();
-    {
-        let mut transfer = easy.transfer();
-        transfer.write_function(|data| {
-            html.extend_from_slice(data);
-            Ok(data.len())
-        })?;
+/// Read the template at the given path parts under `dir`, if it exists.
+fn read_template(dir: &Path, path_parts: &[&str]) -> Result<Option<String>> {
+    let mut file_path = dir.to_path_buf();
+    for path_part in path_parts {
+        file_path.push(path_part);
+    }
 
-        transfer.perform()?;
+    if !file_path.is_file() {
+        return Ok(None);
     }
 
-    Ok(String::from_utf8(html)?)
+    read_to_string(&file_path).map(Some)
 }
 
-/// crates.io Cargo Registry
-const REGISTRY_HOST: &str = "https://crates.io";
+/// The copyright line of the license prefix.
+const COPYRIGHT: &str = "// Copyright (c) {{ year }} {{ name }} developers\n";
 
 /// Cargo.toml package readme entry.
 const CARGO_TOML_README: &str = r#"README.md"#;
 
-/// clap version of `main.rs`
-const CLAP_MAIN_RS: &str = r#"//! `{{ name }}` 0.1.0
+/// `main.rs`
+const MAIN_RS: &str = r#"//! `{{ name }}` 0.1.0
 #![deny(missing_docs)]
+{{#macro_crates}}
 #[macro_use]
-extern crate error_chain;
-extern crate clap;
+extern crate {{ . }};
+{{/macro_crates}}
+{{#crates}}
+extern crate {{ . }};
+{{/crates}}
 
+{{^split}}
 mod error;
+{{/split}}
 mod run;
 
 use std::io::{self, Write};
@@ -302,11 +964,117 @@ fn main() {
     }
 }"#;
 
+/// `lib.rs` of the `-core` package of the split layout
+const LIB_RS: &str = r#"//! `{{ name }}-core` 0.1.0
+#![deny(missing_docs)]
+{{#core_macro_crates}}
+#[macro_use]
+extern crate {{ . }};
+{{/core_macro_crates}}
+{{#core_crates}}
+extern crate {{ . }};
+{{/core_crates}}
+
+pub mod error;
+
+pub use {{ crate_root }}error::*;
+use std::io::{self, Write};
+
+/// Write a greeting for the given name to stdout.
+pub fn greet(name: &str) -> Result<()> {
+    writeln!(io::stdout(), "Hello, {}!", name)?;
+    Ok(())
+}"#;
+
+/// error_chain version of `error.rs`
+const ERROR_CHAIN_ERROR_RS: &str = r#"//! `{{ name }}` errors
+{{#split}}
+// `error_chain!` leaves the variants of the public error kind undocumented.
+#![allow(missing_docs)]
+{{/split}}
+{{^edition_2015}}
+use error_chain::error_chain;
+
+{{/edition_2015}}
+error_chain!{
+    foreign_links {
+{{#foreign}}
+        {{ variant }}({{ path }});
+{{/foreign}}
+        Io(::std::io::Error);
+    }
+}"#;
+
+/// anyhow version of `error.rs`
+const ANYHOW_ERROR_RS: &str = r#"//! `{{ name }}` errors
+pub use anyhow::Result;"#;
+
+/// thiserror version of `error.rs`
+const THISERROR_ERROR_RS: &str = r#"//! `{{ name }}` errors
+
+/// `{{ name }}` errors
+#[derive(Debug, ::thiserror::Error)]
+pub enum Error {
+{{#foreign}}
+    /// A `{{ krate }}` error.
+    #[error(transparent)]
+    {{ variant }}(#[from] {{ path }}),
+{{/foreign}}
+    /// An I/O error.
+    #[error(transparent)]
+    Io(#[from] ::std::io::Error),
+}
+
+/// `{{ name }}` result
+pub type Result<T> = ::std::result::Result<T, Error>;"#;
+
+/// snafu version of `error.rs`
+const SNAFU_ERROR_RS: &str = r#"//! `{{ name }}` errors
+use snafu::Snafu;
+
+/// `{{ name }}` errors
+#[derive(Debug, Snafu)]
+pub enum Error {
+{{#foreign}}
+    /// A `{{ krate }}` error.
+    #[snafu(display("{source}"), context(false))]
+    {{ variant }} {
+        /// The underlying error.
+        source: {{ path }},
+    },
+{{/foreign}}
+    /// An I/O error.
+    #[snafu(display("{source}"), context(false))]
+    Io {
+        /// The underlying error.
+        source: ::std::io::Error,
+    },
+}
+
+/// `{{ name }}` result
+pub type Result<T> = ::std::result::Result<T, Error>;"#;
+
+/// eyre version of `error.rs`
+const EYRE_ERROR_RS: &str = r#"//! `{{ name }}` errors
+pub use eyre::Result;"#;
+
+/// std version of `error.rs`
+const STD_ERROR_RS: &str = r#"//! `{{ name }}` errors
+use std::error::Error;
+
+/// `{{ name }}` result
+pub type Result<T> = ::std::result::Result<T, Box<dyn Error>>;"#;
+
 /// clap version of `run.rs`
 const CLAP_RUN_RS: &str = r#"//! `{{ name }}` runtime
 use clap::App;
-use error::Result;
+{{#split}}
+use {{ core_crate }}::{greet, Result};
+{{/split}}
+{{^split}}
+use {{ crate_root }}error::Result;
 use std::io::{self, Write};
+{{/split}}
 
 /// CLI Runtime
 pub fn run() -> Result<i32> {
@@ -315,48 +1083,77 @@ pub fn run() -> Result<i32> {
                       .author(env!("CARGO_PKG_AUTHORS"))
                       .about("Prints 'Hello, Rustaceans!' to stdout")
                       .get_matches();
+{{#split}}
+    greet("Rustaceans")?;
+{{/split}}
+{{^split}}
     writeln!(io::stdout(), "Hello, Rustaceans!")?;
+{{/split}}
     Ok(0)
 }"#;
 
-/// clap version of `error.rs`
-const CLAP_ERROR_RS: &str = r#"//! `{{ name }}` errors
-error_chain!{
-    foreign_links {
-        Io(::std::io::Error);
-    }
-}"#;
-
-/// docopt version of `main.rs`
-const DOCOPT_MAIN_RS: &str = r#"//! `{{ name }}` 0.1.0
-#[macro_use]
-extern crate error_chain;
-#[macro_use]
-extern crate serde_derive;
-extern crate docopt;
+/// clap derive version of `run.rs`
+const CLAP_DERIVE_RUN_RS: &str = r#"//! `{{ name }}` runtime
+use clap::{Parser, Subcommand};
+{{#split}}
+use {{ core_crate }}::{greet, Result};
+{{/split}}
+{{^split}}
+use {{ crate_root }}error::Result;
+use std::io::{self, Write};
+{{/split}}
 
-mod error;
-mod run;
+/// Command line arguments
+#[derive(Debug, Parser)]
+#[command(author, version, about = "Prints 'Hello, Rustaceans!' to stdout")]
+struct Args {
+    /// The subcommand to run.
+    #[command(subcommand)]
+    command: Option<Command>,
+}
 
-use std::io::{self, Write};
-use std::process;
+/// Subcommands
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Prints a greeting to stdout
+    Hello {
+        /// Who to greet.
+        #[arg(short, long, default_value = "Rustaceans")]
+        name: String,
+    },
+}
 
-/// CLI Entry Point
-fn main() {
-    match run::run() {
-        Ok(i) => process::exit(i),
-        Err(e) => {
-            writeln!(io::stderr(), "{}", e).expect("Unable to write to stderr!");
-            process::exit(1)
-        }
+/// CLI Runtime
+pub fn run() -> Result<i32> {
+    let args = Args::parse();
+    match args.command {
+{{#split}}
+        Some(Command::Hello { name }) => greet(&name)?,
+        None => greet("Rustaceans")?,
+{{/split}}
+{{^split}}
+        Some(Command::Hello { name }) => writeln!(io::stdout(), "Hello, {}!", name)?,
+        None => writeln!(io::stdout(), "Hello, Rustaceans!")?,
+{{/split}}
     }
+    Ok(0)
 }"#;
 
 /// docopt version of `run.rs`
 const DOCOPT_RUN_RS: &str = r#"//! `{{ name }}` runtime
+{{#split}}
+use {{ core_crate }}::{greet, Result};
+{{/split}}
+{{^split}}
+use {{ crate_root }}error::Result;
+{{/split}}
 use docopt::Docopt;
-use error::Result;
+{{^edition_2015}}
+use serde_derive::Deserialize;
+{{/edition_2015}}
+{{^split}}
 use std::io::{self, Write};
+{{/split}}
 
 /// Write the Docopt usage string.
 const USAGE: &str = "
@@ -375,292 +1172,577 @@ struct Args;
 /// CLI Runtime
 pub fn run() -> Result<i32> {
     let _args: Args = Docopt::new(USAGE).and_then(|d| d.deserialize())?;
+{{#split}}
+    greet("Rustaceans")?;
+{{/split}}
+{{^split}}
     writeln!(io::stdout(), "Hello, Rustaceans!")?;
+{{/split}}
     Ok(0)
 }"#;
 
-/// docopt version of `error.rs`
-const DOCOPT_ERROR_RS: &str = r#"//! `{{ name }}` errors
-error_chain!{
-    foreign_links {
-        Docopt(::docopt::Error);
-        Io(::std::io::Error);
+/// lexopt version of `run.rs`
+const LEXOPT_RUN_RS: &str = r#"//! `{{ name }}` runtime
+{{#split}}
+use {{ core_crate }}::{greet, Result};
+{{/split}}
+{{^split}}
+use {{ crate_root }}error::Result;
+{{/split}}
+use lexopt::prelude::*;
+use std::io::{self, Write};
+
+/// Write the usage string.
+const USAGE: &str = "Prints 'Hello, Rustaceans!' to stdout
+
+Usage: {{ name }} [-n | --name <NAME>]
+       {{ name }} ( -h | --help )
+       {{ name }} ( -V | --version )";
+
+/// CLI Runtime
+pub fn run() -> Result<i32> {
+    let mut name = "Rustaceans".to_string();
+    let mut parser = lexopt::Parser::from_env();
+
+    while let Some(arg) = parser.next()? {
+        match arg {
+            Short('n') | Long("name") => name = parser.value()?.string()?,
+            Short('h') | Long("help") => {
+                writeln!(io::stdout(), "{}", USAGE)?;
+                return Ok(0);
+            }
+            Short('V') | Long("version") => {
+                writeln!(io::stdout(), "{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))?;
+                return Ok(0);
+            }
+            _ => return Err(arg.unexpected().into()),
+        }
     }
+
+{{#split}}
+    greet(&name)?;
+{{/split}}
+{{^split}}
+    writeln!(io::stdout(), "Hello, {}!", name)?;
+{{/split}}
+    Ok(0)
 }"#;
 
-/// MIT/Apache-2.0 license entry for Cargo.toml.
-const CARGO_TOML_BOTH: &str = r#"MIT/Apache-2.0"#;
+/// pico-args version of `run.rs`
+const PICO_ARGS_RUN_RS: &str = r#"//! `{{ name }}` runtime
+{{#split}}
+use {{ core_crate }}::{greet, Result};
+{{/split}}
+{{^split}}
+use {{ crate_root }}error::Result;
+{{/split}}
+use pico_args::Arguments;
+use std::io::{self, Write};
 
-/// .rs file prefix when both licenses are used.
-const PREFIX_BOTH: &str = r#"// Copyright (c) 2017 {{ name }} developers
-//
-// Licensed under the Apache License, Version 2.0
-// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
-// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
-// option. All files in the project carrying such notice may not be copied,
-// modified, or distributed except according to those terms.
+/// Write the usage string.
+const USAGE: &str = "Prints 'Hello, Rustaceans!' to stdout
 
-"#;
+Usage: {{ name }} [-n | --name <NAME>]
+       {{ name }} ( -h | --help )
+       {{ name }} ( -V | --version )";
 
-/// MIT license entry for Cargo.toml.
-const CARGO_TOML_MIT: &str = r#"MIT"#;
+/// Command line arguments
+#[derive(Debug)]
+struct Args {
+    /// Who to greet.
+    name: String,
+}
 
-/// .rs file prefix when MIT is the only license.
-const PREFIX_MIT: &str = r#"// Copyright (c) 2017 {{ name }} developers
-//
-// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
-// All files in the project carrying such notice may not be copied,
-// modified, or distributed except according to those terms.
+/// CLI Runtime
+pub fn run() -> Result<i32> {
+    let mut pargs = Arguments::from_env();
 
-"#;
+    if pargs.contains(["-h", "--help"]) {
+        writeln!(io::stdout(), "{}", USAGE)?;
+        return Ok(0);
+    }
 
-/// Apache-2.0 license entry for Cargo.toml.
-const CARGO_TOML_APACHE: &str = r#"Apache-2.0"#;
+    if pargs.contains(["-V", "--version"]) {
+        writeln!(io::stdout(), "{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))?;
+        return Ok(0);
+    }
 
-/// .rs file prefix when Apache-2.0 is the only license.
-const PREFIX_APACHE: &str = r#"// Copyright (c) 2017 {{ name }} developers
-//
-// Licensed under the Apache License, Version 2.0
-// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0>.
-// All files in the project carrying such notice may not be copied,
-// modified, or distributed except according to those terms.
+    let args = Args {
+        name: pargs
+            .opt_value_from_str(["-n", "--name"])?
+            .unwrap_or_else(|| "Rustaceans".to_string()),
+    };
 
-"#;
+    let remaining = pargs.finish();
+    if !remaining.is_empty() {
+        writeln!(io::stderr(), "Unexpected arguments: {:?}\n\n{}", remaining, USAGE)?;
+        return Ok(1);
+    }
 
-/// MIT License template
-const LICENSE_MIT: &str = r#"Copyright (c) 2016 The Rust Project Developers
-
-Permission is hereby granted, free of charge, to any
-person obtaining a copy of this software and associated
-documentation files (the "Software"), to deal in the
-Software without restriction, including without
-limitation the rights to use, copy, modify, merge,
-publish, distribute, sublicense, and/or sell copies of
-the Software, and to permit persons to whom the Software
-is furnished to do so, subject to the following
-conditions:
-
-The above copyright notice and this permission notice
-shall be included in all copies or substantial portions
-of the Software.
-
-THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
-ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
-TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
-PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
-SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
-CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
-OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
-IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
-DEALINGS IN THE SOFTWARE.
+{{#split}}
+    greet(&args.name)?;
+{{/split}}
+{{^split}}
+    writeln!(io::stdout(), "Hello, {}!", args.name)?;
+{{/split}}
+    Ok(0)
+}"#;
 
-"#;
+/// argh version of `run.rs`
+const ARGH_RUN_RS: &str = r#"//! `{{ name }}` runtime
+use argh::FromArgs;
+{{#split}}
+use {{ core_crate }}::{greet, Result};
+{{/split}}
+{{^split}}
+use {{ crate_root }}error::Result;
+use std::io::{self, Write};
+{{/split}}
+
+/// Prints 'Hello, Rustaceans!' to stdout
+#[derive(Debug, FromArgs)]
+struct Args {
+    /// who to greet
+    #[argh(option, short = 'n', default = "String::from(\"Rustaceans\")")]
+    name: String,
+}
 
-/// Apache-2.0 License template
-const LICENSE_APACHE: &str = r#"                              Apache License
-                        Version 2.0, January 2004
-                     http://www.apache.org/licenses/
-
-TERMS AND CONDITIONS FOR USE, REPRODUCTION, AND DISTRIBUTION
-
-1. Definitions.
-
-   "License" shall mean the terms and conditions for use, reproduction,
-   and distribution as defined by Sections 1 through 9 of this document.
-
-   "Licensor" shall mean the copyright owner or entity authorized by
-   the copyright owner that is granting the License.
-
-   "Legal Entity" shall mean the union of the acting entity and all
-   other entities that control, are controlled by, or are under common
-   control with that entity. For the purposes of this definition,
-   "control" means (i) the power, direct or indirect, to cause the
-   direction or management of such entity, whether by contract or
-   otherwise, or (ii) ownership of fifty percent (50%) or more of the
-   outstanding shares, or (iii) beneficial ownership of such entity.
-
-   "You" (or "Your") shall mean an individual or Legal Entity
-   exercising permissions granted by this License.
-
-   "Source" form shall mean the preferred form for making modifications,
-   including but not limited to software source code, documentation
-   source, and configuration files.
-
-   "Object" form shall mean any form resulting from mechanical
-   transformation or translation of a Source form, including but
-   not limited to compiled object code, generated documentation,
-   and conversions to other media types.
-
-   "Work" shall mean the work of authorship, whether in Source or
-   Object form, made available under the License, as indicated by a
-   copyright notice that is included in or attached to the work
-   (an example is provided in the Appendix below).
-
-   "Derivative Works" shall mean any work, whether in Source or Object
-   form, that is based on (or derived from) the Work and for which the
-   editorial revisions, annotations, elaborations, or other modifications
-   represent, as a whole, an original work of authorship. For the purposes
-   of this License, Derivative Works shall not include works that remain
-   separable from, or merely link (or bind by name) to the interfaces of,
-   the Work and Derivative Works thereof.
-
-   "Contribution" shall mean any work of authorship, including
-   the original version of the Work and any modifications or additions
-   to that Work or Derivative Works thereof, that is intentionally
-   submitted to Licensor for inclusion in the Work by the copyright owner
-   or by an individual or Legal Entity authorized to submit on behalf of
-   the copyright owner. For the purposes of this definition, "submitted"
-   means any form of electronic, verbal, or written communication sent
-   to the Licensor or its representatives, including but not limited to
-   communication on electronic mailing lists, source code control systems,
-   and issue tracking systems that are managed by, or on behalf of, the
-   Licensor for the purpose of discussing and improving the Work, but
-   excluding communication that is conspicuously marked or otherwise
-   designated in writing by the copyright owner as "Not a Contribution."
-
-   "Contributor" shall mean Licensor and any individual or Legal Entity
-   on behalf of whom a Contribution has been received by Licensor and
-   subsequently incorporated within the Work.
-
-2. Grant of Copyright License. Subject to the terms and conditions of
-   this License, each Contributor hereby grants to You a perpetual,
-   worldwide, non-exclusive, no-charge, royalty-free, irrevocable
-   copyright license to reproduce, prepare Derivative Works of,
-   publicly display, publicly perform, sublicense, and distribute the
-   Work and such Derivative Works in Source or Object form.
-
-3. Grant of Patent License. Subject to the terms and conditions of
-   this License, each Contributor hereby grants to You a perpetual,
-   worldwide, non-exclusive, no-charge, royalty-free, irrevocable
-   (except as stated in this section) patent license to make, have made,
-   use, offer to sell, sell, import, and otherwise transfer the Work,
-   where such license applies only to those patent claims licensable
-   by such Contributor that are necessarily infringed by their
-   Contribution(s) alone or by combination of their Contribution(s)
-   with the Work to which such Contribution(s) was submitted. If You
-   institute patent litigation against any entity (including a
-   cross-claim or counterclaim in a lawsuit) alleging that the Work
-   or a Contribution incorporated within the Work constitutes direct
-   or contributory patent infringement, then any patent licenses
-   granted to You under this License for that Work shall terminate
-   as of the date such litigation is filed.
-
-4. Redistribution. You may reproduce and distribute copies of the
-   Work or Derivative Works thereof in any medium, with or without
-   modifications, and in Source or Object form, provided that You
-   meet the following conditions:
-
-   (a) You must give any other recipients of the Work or
-       Derivative Works a copy of this License; and
-
-   (b) You must cause any modified files to carry prominent notices
-       stating that You changed the files; and
-
-   (c) You must retain, in the Source form of any Derivative Works
-       that You distribute, all copyright, patent, trademark, and
-       attribution notices from the Source form of the Work,
-       excluding those notices that do not pertain to any part of
-       the Derivative Works; and
-
-   (d) If the Work includes a "NOTICE" text file as part of its
-       distribution, then any Derivative Works that You distribute must
-       include a readable copy of the attribution notices contained
-       within such NOTICE file, excluding those notices that do not
-       pertain to any part of the Derivative Works, in at least one
-       of the following places: within a NOTICE text file distributed
-       as part of the Derivative Works; within the Source form or
-       documentation, if provided along with the Derivative Works; or,
-       within a display generated by the Derivative Works, if and
-       wherever such third-party notices normally appear. The contents
-       of the NOTICE file are for informational purposes only and
-       do not modify the License. You may add Your own attribution
-       notices within Derivative Works that You distribute, alongside
-       or as an addendum to the NOTICE text from the Work, provided
-       that such additional attribution notices cannot be construed
-       as modifying the License.
-
-   You may add Your own copyright statement to Your modifications and
-   may provide additional or different license terms and conditions
-   for use, reproduction, or distribution of Your modifications, or
-   for any such Derivative Works as a whole, provided Your use,
-   reproduction, and distribution of the Work otherwise complies with
-   the conditions stated in this License.
-
-5. Submission of Contributions. Unless You explicitly state otherwise,
-   any Contribution intentionally submitted for inclusion in the Work
-   by You to the Licensor shall be under the terms and conditions of
-   this License, without any additional terms or conditions.
-   Notwithstanding the above, nothing herein shall supersede or modify
-   the terms of any separate license agreement you may have executed
-   with Licensor regarding such Contributions.
-
-6. Trademarks. This License does not grant permission to use the trade
-   names, trademarks, service marks, or product names of the Licensor,
-   except as required for reasonable and customary use in describing the
-   origin of the Work and reproducing the content of the NOTICE file.
-
-7. Disclaimer of Warranty. Unless required by applicable law or
-   agreed to in writing, Licensor provides the Work (and each
-   Contributor provides its Contributions) on an "AS IS" BASIS,
-   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
-   implied, including, without limitation, any warranties or conditions
-   of TITLE, NON-INFRINGEMENT, MERCHANTABILITY, or FITNESS FOR A
-   PARTICULAR PURPOSE. You are solely responsible for determining the
-   appropriateness of using or redistributing the Work and assume any
-   risks associated with Your exercise of permissions under this License.
-
-8. Limitation of Liability. In no event and under no legal theory,
-   whether in tort (including negligence), contract, or otherwise,
-   unless required by applicable law (such as deliberate and grossly
-   negligent acts) or agreed to in writing, shall any Contributor be
-   liable to You for damages, including any direct, indirect, special,
-   incidental, or consequential damages of any character arising as a
-   result of this License or out of the use or inability to use the
-   Work (including but not limited to damages for loss of goodwill,
-   work stoppage, computer failure or malfunction, or any and all
-   other commercial damages or losses), even if such Contributor
-   has been advised of the possibility of such damages.
-
-9. Accepting Warranty or Additional Liability. While redistributing
-   the Work or Derivative Works thereof, You may choose to offer,
-   and charge a fee for, acceptance of support, warranty, indemnity,
-   or other liability obligations and/or rights consistent with this
-   License. However, in accepting such obligations, You may act only
-   on Your own behalf and on Your sole responsibility, not on behalf
-   of any other Contributor, and only if You agree to indemnify,
-   defend, and hold each Contributor harmless for any liability
-   incurred by, or claims asserted against, such Contributor by reason
-   of your accepting any such warranty or additional liability.
-
-END OF TERMS AND CONDITIONS
-
-APPENDIX: How to apply the Apache License to your work.
-
-   To apply the Apache License to your work, attach the following
-   boilerplate notice, with the fields enclosed by brackets "[]"
-   replaced with your own identifying information. (Don't include
-   the brackets!)  The text should be enclosed in the appropriate
-   comment syntax for the file format. We also recommend that a
-   file or class name and description of purpose be included on the
-   same "printed page" as the copyright notice for easier
-   identification within third-party archives.
-
-Copyright [yyyy] [name of copyright owner]
-
-Licensed under the Apache License, Version 2.0 (the "License");
-you may not use this file except in compliance with the License.
-You may obtain a copy of the License at
-
-	http://www.apache.org/licenses/LICENSE-2.0
-
-Unless required by applicable law or agreed to in writing, software
-distributed under the License is distributed on an "AS IS" BASIS,
-WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
-See the License for the specific language governing permissions and
-limitations under the License.
-"#;
+/// CLI Runtime
+pub fn run() -> Result<i32> {
+    let args: Args = argh::from_env();
+{{#split}}
+    greet(&args.name)?;
+{{/split}}
+{{^split}}
+    writeln!(io::stdout(), "Hello, {}!", args.name)?;
+{{/split}}
+    Ok(0)
+}"#;
+
+/// bpaf version of `run.rs`
+const BPAF_RUN_RS: &str = r#"//! `{{ name }}` runtime
+use bpaf::{construct, long, Parser};
+{{#split}}
+use {{ core_crate }}::{greet, Result};
+{{/split}}
+{{^split}}
+use {{ crate_root }}error::Result;
+use std::io::{self, Write};
+{{/split}}
+
+/// Command line arguments
+#[derive(Clone, Debug)]
+struct Args {
+    /// Who to greet.
+    name: String,
+}
+
+/// CLI Runtime
+pub fn run() -> Result<i32> {
+    let name = long("name")
+        .short('n')
+        .help("Who to greet")
+        .argument::<String>("NAME")
+        .fallback("Rustaceans".to_string());
+    let args = construct!(Args { name })
+        .to_options()
+        .descr("Prints 'Hello, Rustaceans!' to stdout")
+        .version(env!("CARGO_PKG_VERSION"))
+        .run();
+{{#split}}
+    greet(&args.name)?;
+{{/split}}
+{{^split}}
+    writeln!(io::stdout(), "Hello, {}!", args.name)?;
+{{/split}}
+    Ok(0)
+}"#;
 
 /// README.md template
 const README: &str = r#"# {{ name }}
 A Rust command line interface generated by `cargo-cli`.
 "#;
+
+#[cfg(test)]
+mod tests {
+    use super::{ArgParser, Edition, ErrorCrate, Layout, Templates};
+    use error::ErrorKind;
+    use license::{self, License};
+    use manifest::{DepKind, DepTable};
+    use semver::Version;
+    use std::collections::BTreeMap;
+    use std::fs;
+    use tempfile::TempDir;
+
+    const ARG_PARSERS: [ArgParser; 7] = [
+        ArgParser::Clap,
+        ArgParser::ClapDerive,
+        ArgParser::Docopt,
+        ArgParser::Lexopt,
+        ArgParser::PicoArgs,
+        ArgParser::Argh,
+        ArgParser::Bpaf,
+    ];
+
+    const ERROR_CRATES: [ErrorCrate; 6] = [
+        ErrorCrate::ErrorChain,
+        ErrorCrate::Anyhow,
+        ErrorCrate::Thiserror,
+        ErrorCrate::Snafu,
+        ErrorCrate::Eyre,
+        ErrorCrate::Std,
+    ];
+
+    const EDITIONS: [Edition; 4] = [
+        Edition::Edition2015,
+        Edition::Edition2018,
+        Edition::Edition2021,
+        Edition::Edition2024,
+    ];
+
+    /// Create the templates of a `demo-app` project without licenses, README.md or version lookups.
+    fn templates(arg_parser: ArgParser, errors: ErrorCrate, edition: Edition) -> Templates {
+        let mut templates = Templates::new("demo-app", arg_parser, errors, &[], false, false);
+        templates.set_edition(edition);
+        templates
+    }
+
+    /// Get the `[dependencies]` entries of the given templates, as `name = entry` lines.
+    fn dependencies(templates: &Templates) -> Vec<String> {
+        let mut deps = BTreeMap::new();
+        templates.add_deps(&mut deps);
+        deps.remove(&DepTable::new(DepKind::Normal))
+            .unwrap_or_default()
+            .iter()
+            .map(|(name, dep)| format!("{} = {}", name, dep))
+            .collect()
+    }
+
+    /// Does the source hold the given line?
+    fn has_line(source: &str, line: &str) -> bool {
+        source.lines().any(|source_line| source_line == line)
+    }
+
+    #[test]
+    fn mit_license_credits_the_package_this_year() {
+        let templates = Templates::new(
+            "demo",
+            ArgParser::Clap,
+            ErrorCrate::ErrorChain,
+            &[License::Mit],
+            false,
+            false,
+        );
+        let text = templates
+            .license_file("LICENSE-MIT")
+            .expect("No MIT license file!")
+            .expect("Unable to render the MIT license!");
+
+        assert!(text.starts_with(&format!(
+            "Copyright (c) {} demo developers\n",
+            license::current_year()
+        )));
+        assert!(!text.contains("{{"));
+        assert!(!text.contains("2016"));
+        assert!(!text.contains("Rust Project"));
+    }
+
+    #[test]
+    fn clap_derive_parses_into_a_struct() {
+        let templates = templates(
+            ArgParser::ClapDerive,
+            ErrorCrate::Anyhow,
+            Edition::Edition2021,
+        );
+        let run = templates.run().expect("Unable to render run.rs!");
+
+        assert!(run.contains("use clap::{Parser, Subcommand};\n"));
+        assert!(run.contains("#[derive(Debug, Parser)]\n"));
+        assert!(run.contains("#[command(subcommand)]\n"));
+        assert!(run.contains("let args = Args::parse();"));
+        assert!(!run.contains("App::new"));
+        assert_eq!(
+            dependencies(&templates),
+            vec![
+                "anyhow = \"1.0.0\"",
+                "clap = { version = \"4.0.0\", features = [\"derive\"] }",
+            ]
+        );
+    }
+
+    #[test]
+    fn lightweight_parsers_use_their_own_crates() {
+        let parsers = [
+            (
+                ArgParser::Lexopt,
+                "use lexopt::prelude::*;",
+                "lexopt = \"0.3.0\"",
+            ),
+            (
+                ArgParser::PicoArgs,
+                "use pico_args::Arguments;",
+                "pico-args = \"0.5.0\"",
+            ),
+            (ArgParser::Argh, "use argh::FromArgs;", "argh = \"0.1.12\""),
+            (
+                ArgParser::Bpaf,
+                "use bpaf::{construct, long, Parser};",
+                "bpaf = \"0.9.0\"",
+            ),
+        ];
+
+        for &(arg_parser, use_line, dependency) in &parsers {
+            let templates = templates(arg_parser, ErrorCrate::Std, Edition::Edition2021);
+            let run = templates.run().expect("Unable to render run.rs!");
+
+            assert!(run.contains(&format!("{}\n", use_line)), "{}", run);
+            assert!(!run.contains("clap") && !run.contains("docopt"), "{}", run);
+            assert_eq!(dependencies(&templates), vec![dependency]);
+        }
+    }
+
+    #[test]
+    fn parser_errors_get_a_variant() {
+        let variants = [
+            (
+                ArgParser::Lexopt,
+                ErrorCrate::ErrorChain,
+                "Lexopt(::lexopt::Error);",
+            ),
+            (
+                ArgParser::PicoArgs,
+                ErrorCrate::ErrorChain,
+                "PicoArgs(::pico_args::Error);",
+            ),
+            (
+                ArgParser::Lexopt,
+                ErrorCrate::Thiserror,
+                "Lexopt(#[from] ::lexopt::Error),",
+            ),
+            (
+                ArgParser::PicoArgs,
+                ErrorCrate::Snafu,
+                "source: ::pico_args::Error,",
+            ),
+        ];
+
+        for &(arg_parser, errors, variant) in &variants {
+            let templates = templates(arg_parser, errors, Edition::Edition2021);
+            let error = templates.error().expect("Unable to render error.rs!");
+            assert!(error.contains(variant), "{}", error);
+        }
+
+        for &arg_parser in &[ArgParser::Argh, ArgParser::Bpaf] {
+            let templates = templates(arg_parser, ErrorCrate::ErrorChain, Edition::Edition2021);
+            let error = templates.error().expect("Unable to render error.rs!");
+            assert!(
+                !error.contains("::argh") && !error.contains("::bpaf"),
+                "{}",
+                error
+            );
+        }
+    }
+
+    #[test]
+    fn every_combination_follows_the_edition() {
+        for &arg_parser in &ARG_PARSERS {
+            for &errors in &ERROR_CRATES {
+                for &edition in &EDITIONS {
+                    let combination = format!("{} {} {}", arg_parser, errors, edition);
+                    let edition_2015 = edition == Edition::Edition2015;
+                    let templates = templates(arg_parser, errors, edition);
+                    let main = templates.main().expect("Unable to render main.rs!");
+                    let run = templates.run().expect("Unable to render run.rs!");
+                    let error = templates.error().expect("Unable to render error.rs!");
+
+                    assert_eq!(
+                        main.contains("extern crate"),
+                        edition_2015,
+                        "{}",
+                        combination
+                    );
+                    let result_path = if edition_2015 {
+                        "error"
+                    } else {
+                        "crate::error"
+                    };
+                    assert!(
+                        run.contains(&format!("\nuse {}::Result;\n", result_path)),
+                        "{}",
+                        combination
+                    );
+                    assert_eq!(
+                        error.contains("use error_chain::error_chain;"),
+                        errors == ErrorCrate::ErrorChain && !edition_2015,
+                        "{}",
+                        combination
+                    );
+                    assert_eq!(
+                        run.contains("use serde_derive::Deserialize;"),
+                        arg_parser == ArgParser::Docopt && !edition_2015,
+                        "{}",
+                        combination
+                    );
+                    assert_eq!(
+                        templates
+                            .render("edition = \"{{ edition }}\"")
+                            .expect("Unable to render the edition!"),
+                        format!("edition = \"{}\"", edition)
+                    );
+
+                    let deps = dependencies(&templates);
+                    let mut wanted = arg_parser
+                        .dependencies()
+                        .iter()
+                        .map(|&(name, _, _)| name)
+                        .chain(errors.dependency().map(|(name, _)| name))
+                        .collect::<Vec<_>>();
+                    wanted.sort();
+                    let names = deps
+                        .iter()
+                        .map(|dep| dep.split(" = ").next().unwrap_or(""))
+                        .collect::<Vec<_>>();
+                    assert_eq!(names, wanted, "{}", combination);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn edition_2015_crates_are_declared_in_main() {
+        let main = templates(
+            ArgParser::Clap,
+            ErrorCrate::ErrorChain,
+            Edition::Edition2015,
+        )
+        .main()
+        .expect("Unable to render main.rs!");
+        assert!(main.contains("#[macro_use]\nextern crate error_chain;\n"));
+        assert!(has_line(&main, "extern crate clap;"));
+
+        let main = templates(
+            ArgParser::Docopt,
+            ErrorCrate::Thiserror,
+            Edition::Edition2015,
+        )
+        .main()
+        .expect("Unable to render main.rs!");
+        assert!(main.contains("#[macro_use]\nextern crate serde_derive;\n"));
+        for krate in &["docopt", "core", "thiserror"] {
+            assert!(has_line(&main, &format!("extern crate {};", krate)));
+        }
+    }
+
+    #[test]
+    fn split_packages_use_the_core_crate() {
+        for &edition in &EDITIONS {
+            let mut templates = templates(ArgParser::Lexopt, ErrorCrate::ErrorChain, edition);
+            templates.set_layout(Layout::Split);
+            let edition_2015 = edition == Edition::Edition2015;
+            let main = templates.main().expect("Unable to render main.rs!");
+            let run = templates.run().expect("Unable to render run.rs!");
+            let lib = templates.lib().expect("Unable to render lib.rs!");
+
+            assert_eq!(has_line(&main, "extern crate lexopt;"), edition_2015);
+            assert_eq!(has_line(&main, "extern crate demo_app_core;"), edition_2015);
+            assert!(!main.contains("mod error;"));
+            assert!(run.contains("use demo_app_core::{greet, Result};\n"));
+            let error_path = if edition_2015 {
+                "error"
+            } else {
+                "crate::error"
+            };
+            assert!(lib.contains(&format!("pub use {}::*;\n", error_path)));
+            assert_eq!(
+                lib.contains("#[macro_use]\nextern crate error_chain;\n"),
+                edition_2015
+            );
+            assert_eq!(has_line(&lib, "extern crate lexopt;"), edition_2015);
+            assert_eq!(
+                dependencies(&templates),
+                vec![
+                    "demo-app-core = { version = \"0.1.0\", path = \"../demo-app-core\" }",
+                    "lexopt = \"0.3.0\"",
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn editions_follow_the_rust_version() {
+        let newest_for = |rust_version: &str| {
+            Edition::newest_for(&Version::parse(rust_version).expect("Invalid version!"))
+        };
+
+        assert_eq!(newest_for("1.30.0"), Edition::Edition2015);
+        assert_eq!(newest_for("1.31.0"), Edition::Edition2018);
+        assert_eq!(newest_for("1.55.1"), Edition::Edition2018);
+        assert_eq!(newest_for("1.56.0"), Edition::Edition2021);
+        assert_eq!(newest_for("1.84.1"), Edition::Edition2021);
+        assert_eq!(newest_for("1.85.0"), Edition::Edition2024);
+    }
+
+    #[test]
+    fn template_dirs_fall_back_to_the_builtin_templates() {
+        let dir = TempDir::new().expect("Unable to create the directory!");
+        fs::create_dir(dir.path().join("src")).expect("Unable to create src!");
+        fs::write(dir.path().join("src/run.rs"), "// {{ name }} runs here\n")
+            .expect("Unable to write run.rs!");
+        fs::write(dir.path().join("LICENSE-MIT"), "MIT for {{ name }}\n")
+            .expect("Unable to write LICENSE-MIT!");
+
+        let mut templates = Templates::new(
+            "demo",
+            ArgParser::Clap,
+            ErrorCrate::ErrorChain,
+            &[License::Mit, License::Apache],
+            true,
+            false,
+        );
+        templates
+            .load_dir(dir.path())
+            .expect("Unable to load the templates!");
+
+        assert_eq!(templates.run().expect("No run.rs!"), "// demo runs here\n");
+        assert_eq!(
+            templates
+                .license_file("LICENSE-MIT")
+                .expect("No MIT license file!")
+                .expect("Unable to render the MIT license!"),
+            "MIT for demo\n"
+        );
+        assert!(templates
+            .main()
+            .expect("No main.rs!")
+            .starts_with("//! `demo` 0.1.0\n"));
+        assert!(templates
+            .error()
+            .expect("No error.rs!")
+            .contains("error_chain!{"));
+        assert!(templates
+            .license_file("LICENSE-APACHE")
+            .expect("No Apache license file!")
+            .expect("Unable to render the Apache license!")
+            .contains("Apache License"));
+        assert!(templates
+            .readme()
+            .expect("No README.md!")
+            .expect("Unable to render README.md!")
+            .starts_with("# demo\n"));
+        assert!(!templates.has_pack());
+    }
+
+    #[test]
+    fn template_dirs_must_exist() {
+        let dir = TempDir::new().expect("Unable to create the directory!");
+        let missing = dir.path().join("missing");
+        let mut templates = templates(ArgParser::Clap, ErrorCrate::Std, Edition::Edition2021);
+
+        match *templates.load_dir(&missing).unwrap_err().kind() {
+            ErrorKind::InvalidTemplateDir(ref path) => {
+                assert_eq!(*path, missing.display().to_string())
+            }
+            ref kind => panic!("unexpected error: {}", kind),
+        }
+    }
+}
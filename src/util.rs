@@ -0,0 +1,22 @@
+// Copyright (c) 2017 cargo-cli developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! `cargo-cli` file system helpers.
+use error::Result;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+/// Read the file at the given path into a string.
+pub fn read_to_string(path: &Path) -> Result<String> {
+    let mut contents = String::new();
+    let file = File::open(path)?;
+    let mut reader = BufReader::new(file);
+    reader.read_to_string(&mut contents)?;
+    Ok(contents)
+}
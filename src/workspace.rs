@@ -0,0 +1,213 @@
+// Copyright (c) 2017 cargo-cli developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! `cargo-cli` enclosing workspace.
+//!
+//! A package generated inside a Cargo workspace is registered as one of its `members`, inherits
+//! the `[workspace.package]` keys the workspace defines, and takes its registry dependencies from
+//! `[workspace.dependencies]` when the workspace has that table.
+
+use error::Result;
+use manifest::{Dependency, Lint, Manifest};
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
+
+/// The `[package]` keys a member can inherit from `[workspace.package]`.
+pub const INHERITABLE_KEYS: [&str; 12] = [
+    "version",
+    "edition",
+    "rust-version",
+    "authors",
+    "description",
+    "documentation",
+    "homepage",
+    "repository",
+    "license",
+    "keywords",
+    "categories",
+    "publish",
+];
+
+/// The workspace enclosing a generated package.
+pub struct Workspace {
+    /// The path to the workspace `Cargo.toml`.
+    manifest_path: PathBuf,
+    /// The workspace manifest.
+    manifest: Manifest,
+    /// The path of the package, relative to the workspace root.
+    member: String,
+}
+
+impl Workspace {
+    /// Find the workspace the package at the given path belongs to, if any.  The nearest
+    /// enclosing workspace is used, unless it excludes the package.
+    pub fn find(path: &Path) -> Result<Option<Workspace>> {
+        for root in path.ancestors().skip(1) {
+            let manifest_path = root.join("Cargo.toml");
+            if !manifest_path.is_file() {
+                continue;
+            }
+
+            let manifest = Manifest::open(&manifest_path)?;
+            if !manifest.is_workspace() {
+                continue;
+            }
+
+            let member = relative_path(root, path);
+            let excluded = manifest
+                .workspace_paths("exclude")
+                .iter()
+                .any(|exclude| member == *exclude || member.starts_with(&format!("{}/", exclude)));
+            if excluded {
+                return Ok(None);
+            }
+
+            return Ok(Some(Workspace {
+                manifest_path,
+                manifest,
+                member,
+            }));
+        }
+
+        Ok(None)
+    }
+
+    /// Get the `manifest_path` value.
+    pub fn manifest_path(&self) -> &Path {
+        &self.manifest_path
+    }
+
+    /// Get the `member` value.
+    pub fn member(&self) -> &str {
+        &self.member
+    }
+
+    /// Add the package to the workspace `members`, unless one of them already matches it.
+    /// Returns whether the package was added.
+    pub fn add_member(&mut self) -> Result<bool> {
+        let listed = self
+            .manifest
+            .workspace_paths("members")
+            .iter()
+            .any(|pattern| glob_match(pattern, &self.member));
+
+        if listed {
+            Ok(false)
+        } else {
+            self.manifest.add_workspace_member(&self.member)?;
+            Ok(true)
+        }
+    }
+
+    /// Does the workspace define the given `[package]` key for members to inherit?
+    pub fn defines(&self, key: &str) -> bool {
+        self.manifest.workspace_defines(key)
+    }
+
+    /// Get a string value from the `[workspace.package]` table.
+    pub fn package_value(&self, key: &str) -> Option<&str> {
+        self.manifest.workspace_package_value(key)
+    }
+
+    /// Does the workspace share its dependencies through `[workspace.dependencies]`?
+    pub fn has_dependencies(&self) -> bool {
+        self.manifest.has_workspace_dependencies()
+    }
+
+    /// Route the given dependency through `[workspace.dependencies]`, keeping any entry the
+    /// workspace already has.  Returns the member entry inheriting it.
+    pub fn add_dependency(&mut self, name: &str, dep: &Dependency) -> Result<Dependency> {
+        let (shared, member) = dep.inherit();
+        self.manifest.add_workspace_dependency(name, &shared)?;
+        Ok(member)
+    }
+
+    /// Add the given lints of the given tool to `[workspace.lints]`, for a member that inherits
+    /// its lints.  Lints the workspace already sets are kept.
+    pub fn add_lints(&mut self, tool: &str, lints: &BTreeMap<String, Lint>) -> Result<()> {
+        self.manifest.add_workspace_lints(tool, lints)
+    }
+
+    /// Get the workspace manifest contents.
+    pub fn manifest(&self) -> String {
+        self.manifest.to_string()
+    }
+}
+
+/// Get the given path relative to the given root, with `/` separators as in `Cargo.toml`.
+fn relative_path(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .components()
+        .filter_map(|component| match component {
+            Component::Normal(part) => Some(part.to_string_lossy().into_owned()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Does the path match the `members` glob, where `*` and `?` match within a path component?
+fn glob_match(pattern: &str, path: &str) -> bool {
+    let patterns = pattern.trim_end_matches('/').split('/').collect::<Vec<_>>();
+    let parts = path.split('/').collect::<Vec<_>>();
+
+    patterns.len() == parts.len()
+        && patterns
+            .iter()
+            .zip(&parts)
+            .all(|(pattern, part)| component_match(pattern.as_bytes(), part.as_bytes()))
+}
+
+/// Does the path component match the glob component?
+fn component_match(pattern: &[u8], part: &[u8]) -> bool {
+    match (pattern.first(), part.first()) {
+        (None, None) => true,
+        (Some(b'*'), _) => {
+            component_match(&pattern[1..], part)
+                || (!part.is_empty() && component_match(pattern, &part[1..]))
+        }
+        (Some(b'?'), Some(_)) => component_match(&pattern[1..], &part[1..]),
+        (Some(p), Some(c)) if p == c => component_match(&pattern[1..], &part[1..]),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::glob_match;
+
+    #[test]
+    fn literal_members_match_exactly() {
+        assert!(glob_match("cli", "cli"));
+        assert!(glob_match("crates/cli/", "crates/cli"));
+        assert!(!glob_match("cli", "cli-core"));
+        assert!(!glob_match("crates/cli", "crates/cli/src"));
+    }
+
+    #[test]
+    fn stars_match_within_a_component() {
+        assert!(glob_match("crates/*", "crates/cli"));
+        assert!(glob_match("crates/*", "crates/a"));
+        assert!(glob_match("crates/cli-*", "crates/cli-"));
+        assert!(glob_match("*/cli", "tools/cli"));
+        assert!(glob_match("crates/*-core", "crates/cli-core"));
+        assert!(!glob_match("crates/*", "crates/cli/nested"));
+        assert!(!glob_match("crates/*", "tools/cli"));
+        assert!(!glob_match("crates/*-core", "crates/cli"));
+    }
+
+    #[test]
+    fn question_marks_match_one_character() {
+        assert!(glob_match("crates/cli?", "crates/cli2"));
+        assert!(glob_match("crates/?li", "crates/cli"));
+        assert!(!glob_match("crates/cli?", "crates/cli"));
+        assert!(!glob_match("crates/cli?", "crates/cli22"));
+        assert!(!glob_match("crates?cli", "crates/cli"));
+    }
+}